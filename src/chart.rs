@@ -0,0 +1,134 @@
+use crate::HeatmapSegment;
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+pub const CHART_WIDTH: u32 = 1200;
+pub const CHART_HEIGHT: u32 = 300;
+/// Empty pixels around the plot area
+const MARGIN: u32 = 16;
+
+const BACKGROUND: [u8; 3] = [24, 24, 28];
+const AXIS: [u8; 3] = [90, 90, 96];
+const INTENSITY: [u8; 3] = [255, 140, 0];
+const SELECTED: [u8; 3] = [60, 110, 200];
+/// Opacity of the bands marking selected clips
+const SELECTED_ALPHA: f64 = 0.35;
+
+/// RGB pixel buffer the chart is drawn into
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, color: [u8; 3]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat((width * height) as usize),
+        }
+    }
+
+    /// Blend `color` over the rectangle `[x0, x1) x [y0, y1)`, clipped to the canvas
+    fn fill(&mut self, (x0, x1): (u32, u32), (y0, y1): (u32, u32), color: [u8; 3], alpha: f64) {
+        for y in y0.min(self.height)..y1.min(self.height) {
+            for x in x0.min(self.width)..x1.min(self.width) {
+                let at = ((y * self.width + x) * 3) as usize;
+                for (channel, value) in color.iter().enumerate() {
+                    let old = self.pixels[at + channel] as f64;
+                    self.pixels[at + channel] = (old + (*value as f64 - old) * alpha).round() as u8;
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let at = ((y * self.width + x) * 3) as usize;
+        [self.pixels[at], self.pixels[at + 1], self.pixels[at + 2]]
+    }
+}
+
+/// Draw heatmap intensity against time, with vertical bands for the selected clips.
+///
+/// `selected` holds `(start, end)` ranges in seconds. The time axis spans from 0 to the
+/// end of the last segment or clip.
+fn draw(segments: &[HeatmapSegment], selected: &[(f64, f64)]) -> Canvas {
+    let mut canvas = Canvas::new(CHART_WIDTH, CHART_HEIGHT, BACKGROUND);
+    let plot_width = (CHART_WIDTH - 2 * MARGIN) as f64;
+    let plot_height = (CHART_HEIGHT - 2 * MARGIN) as f64;
+    let baseline = CHART_HEIGHT - MARGIN;
+
+    let total = segments
+        .iter()
+        .map(|segment| segment.start + segment.duration)
+        .chain(selected.iter().map(|&(_, end)| end))
+        .fold(0.0_f64, f64::max);
+    if total <= 0.0 {
+        return canvas;
+    }
+    // Every range gets at least one pixel column, however short
+    let columns = |start: f64, end: f64| {
+        let x0 = MARGIN + (start.max(0.0) / total * plot_width) as u32;
+        let x1 = MARGIN + (end.min(total) / total * plot_width).ceil() as u32;
+        (x0, x1.max(x0 + 1))
+    };
+
+    for &(start, end) in selected {
+        canvas.fill(columns(start, end), (MARGIN, baseline), SELECTED, SELECTED_ALPHA);
+    }
+    for segment in segments {
+        let height = (segment.score.clamp(0.0, 1.0) * plot_height).round() as u32;
+        canvas.fill(
+            columns(segment.start, segment.start + segment.duration),
+            (baseline - height, baseline),
+            INTENSITY,
+            1.0,
+        );
+    }
+    canvas.fill((MARGIN, CHART_WIDTH - MARGIN), (baseline, baseline + 1), AXIS, 1.0);
+    canvas
+}
+
+/// Render the engagement curve as a PNG at `out_path`, marking `selected` clip ranges.
+pub fn render_heatmap_png(segments: &[HeatmapSegment], selected: &[(f64, f64)], out_path: &Path) -> Result<()> {
+    let canvas = draw(segments, selected);
+    let file = File::create(out_path).map_err(|e| anyhow!("Cannot create {}: {}", out_path.display(), e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), canvas.width, canvas.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&canvas.pixels)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_marks_segments_and_selection() {
+        let segments = vec![
+            HeatmapSegment { start: 0.0, duration: 50.0, score: 0.5 },
+            HeatmapSegment { start: 50.0, duration: 50.0, score: 1.0 },
+        ];
+        let canvas = draw(&segments, &[(50.0, 100.0)]);
+        let baseline = CHART_HEIGHT - MARGIN;
+
+        // Full-height bar inside the selected band, half-height bar outside it
+        assert_eq!(canvas.pixel(CHART_WIDTH * 3 / 4, MARGIN + 1), INTENSITY);
+        assert_eq!(canvas.pixel(CHART_WIDTH / 4, baseline - 10), INTENSITY);
+        assert_eq!(canvas.pixel(CHART_WIDTH / 4, MARGIN + 1), BACKGROUND);
+        assert_eq!(canvas.pixel(CHART_WIDTH / 4, baseline), AXIS);
+
+        let path = std::env::temp_dir().join(format!("yt-clipper-heatmap-{}.png", std::process::id()));
+        render_heatmap_png(&segments, &[(50.0, 100.0)], &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(bytes.starts_with(b"\x89PNG"));
+    }
+}
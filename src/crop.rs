@@ -1,80 +1,405 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString};
 
-/// Height for top section (center content) in split mode
-pub const TOP_HEIGHT: u32 = 960;
+use crate::subtitle::SubtitlePosition;
 
-/// Height for bottom section (facecam) in split mode
-pub const BOTTOM_HEIGHT: u32 = 350;
+/// Share of the output height given to the top section (center content) in split mode;
+/// the bottom section (facecam) gets the remainder. 0.75 gives 960 + 320 at 1280.
+pub const DEFAULT_SPLIT_RATIO: f64 = 0.75;
 
 /// Output video dimensions
 pub const OUTPUT_WIDTH: u32 = 720;
 pub const OUTPUT_HEIGHT: u32 = 1280;
 
-/// Crop mode for video processing
-#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+/// Side length for square output
+pub const SQUARE_SIZE: u32 = 1080;
+
+/// Output frame size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputSpec {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for OutputSpec {
+    fn default() -> Self {
+        Self {
+            width: OUTPUT_WIDTH,
+            height: OUTPUT_HEIGHT,
+        }
+    }
+}
+
+impl OutputSpec {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    /// Square frame with the given side length
+    pub fn square(size: u32) -> Self {
+        Self::new(size, size)
+    }
+
+    /// Parse from user input ("WIDTHxHEIGHT", or a single number for a square)
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        let spec = match input.split_once('x') {
+            Some((w, h)) => Self::new(w.trim().parse().ok()?, h.trim().parse().ok()?),
+            None => Self::square(input.parse().ok()?),
+        };
+        // libx264 requires even dimensions
+        if spec.width == 0 || spec.height == 0 || spec.width % 2 != 0 || spec.height % 2 != 0 {
+            return None;
+        }
+        Some(spec)
+    }
+}
+
+/// Smallest share of the cover-scaled source kept by the default mode's zoom
+pub const MIN_ZOOM: f64 = 0.25;
+
+/// How the default crop mode fits the source into the output frame
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Display, EnumString,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FitMode {
+    /// Scale to cover the frame and crop the overflow
+    #[default]
+    Fill,
+    /// Scale to fit inside the frame and letterbox the rest with black bars
+    Pad,
+}
+
+impl FitMode {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "fill" | "crop" => Some(FitMode::Fill),
+            "pad" | "letterbox" => Some(FitMode::Pad),
+            _ => None,
+        }
+    }
+}
+
+/// Tuning for the split (content + facecam) layouts, and the default mode's zoom and fit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SplitLayout {
+    /// Magnification of the facecam corner; values above 1.0 crop a smaller corner region
+    /// and scale it up to fill the bottom section
+    pub facecam_zoom: f64,
+    /// Fraction of the output height used by the top section
+    pub split_ratio: f64,
+    /// Default mode only: share of the source height kept around the center. 1.0 keeps
+    /// the full height (the frame is just filled); lower values zoom in further.
+    pub zoom: f64,
+    /// Default mode only: crop to fill the frame, or letterbox the whole source (which
+    /// ignores `zoom`)
+    pub fit: FitMode,
+}
+
+impl Default for SplitLayout {
+    fn default() -> Self {
+        Self {
+            facecam_zoom: 1.0,
+            split_ratio: DEFAULT_SPLIT_RATIO,
+            zoom: 1.0,
+            fit: FitMode::Fill,
+        }
+    }
+}
+
+impl SplitLayout {
+    /// Heights of the top and bottom sections; they always add up to `height`
+    pub fn section_heights(&self, height: u32) -> (u32, u32) {
+        let ratio = if self.split_ratio.is_finite() {
+            self.split_ratio
+        } else {
+            DEFAULT_SPLIT_RATIO
+        };
+        let top = even(height as f64 * ratio).clamp(2, height.saturating_sub(2).max(2));
+        (top, height - top)
+    }
+}
+
+/// Round down to an even number (libx264 needs even crop sizes)
+fn even(value: f64) -> u32 {
+    (value.max(2.0) as u32) & !1
+}
+
+/// Corner of the source video the facecam is cropped from
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Display, EnumString,
+)]
 #[strum(serialize_all = "kebab-case")]
 #[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    #[default]
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+}
+
+impl Corner {
+    /// FFmpeg `crop` x offset for a region `width` pixels wide
+    fn crop_x(&self, width: u32) -> String {
+        match self {
+            Corner::BottomLeft | Corner::TopLeft => "0".to_string(),
+            Corner::BottomRight | Corner::TopRight => format!("iw-{}", width),
+        }
+    }
+
+    /// FFmpeg `crop` y offset for a region `height` pixels tall
+    fn crop_y(&self, height: u32) -> String {
+        match self {
+            Corner::TopLeft | Corner::TopRight => "0".to_string(),
+            Corner::BottomLeft | Corner::BottomRight => format!("ih-{}", height),
+        }
+    }
+}
+
+/// Crop mode for video processing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub enum CropMode {
     /// Standard center crop - takes center portion of video
     #[default]
     Default,
-    /// Split crop: top = center content, bottom = bottom-left corner (facecam)
-    SplitLeft,
-    /// Split crop: top = center content, bottom = bottom-right corner (facecam)
-    SplitRight,
+    /// Split crop: top = center content, bottom = facecam cropped from a corner
+    Split { facecam: Corner },
+    /// Square 1:1 center crop (Instagram feed)
+    Square,
+    /// Vertical crop shifted sideways to where the motion is, fixed for the whole clip.
+    ///
+    /// `offset` is how far the moving content's center sits from the frame's center, in
+    /// source pixels (negative = left). It is `None` until a clip has been analyzed (see
+    /// `parse_content_offset`), which crops the center like `Default`.
+    MotionTrack { offset: Option<i32> },
 }
 
+#[allow(non_upper_case_globals)]
 impl CropMode {
-    /// Get the FFmpeg video filter string for this crop mode
+    /// Split crop with the facecam in the bottom-left corner
+    #[deprecated(note = "use CropMode::Split { facecam: Corner::BottomLeft }")]
+    pub const SplitLeft: CropMode = CropMode::Split {
+        facecam: Corner::BottomLeft,
+    };
+
+    /// Split crop with the facecam in the bottom-right corner
+    #[deprecated(note = "use CropMode::Split { facecam: Corner::BottomRight }")]
+    pub const SplitRight: CropMode = CropMode::Split {
+        facecam: Corner::BottomRight,
+    };
+}
+
+impl std::fmt::Display for CropMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CropMode::Default => f.write_str("default"),
+            CropMode::Square => f.write_str("square"),
+            CropMode::MotionTrack { .. } => f.write_str("motion-track"),
+            // Bottom corners keep their original names so existing output stays the same
+            CropMode::Split {
+                facecam: Corner::BottomLeft,
+            } => f.write_str("split-left"),
+            CropMode::Split {
+                facecam: Corner::BottomRight,
+            } => f.write_str("split-right"),
+            CropMode::Split { facecam } => write!(f, "split-{}", facecam),
+        }
+    }
+}
+
+impl std::str::FromStr for CropMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        CropMode::from_input(input).ok_or_else(|| format!("unknown crop mode '{}'", input))
+    }
+}
+
+/// Crop modes travel as their names (see `Display`), so the schema lists those
+impl schemars::JsonSchema for CropMode {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "CropMode".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let names: Vec<String> = CropMode::all().iter().map(CropMode::to_string).collect();
+        schemars::json_schema!({
+            "type": "string",
+            "enum": names
+        })
+    }
+}
+
+impl From<CropMode> for String {
+    fn from(mode: CropMode) -> Self {
+        mode.to_string()
+    }
+}
+
+impl TryFrom<String> for CropMode {
+    type Error = String;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+/// A crop mode as listed to users (`--list-crop-modes`, the server's health payload)
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct CropModeInfo {
+    /// Name accepted by `CropMode::from_input`
+    pub id: String,
+    pub description: String,
+    /// Whether the mode encodes through a multi-stream filter graph, which is slow on the
+    /// CPU and gains the most from `--gpu`
+    pub gpu_recommended: bool,
+}
+
+impl CropMode {
+    /// Every crop mode, numbered as in `from_input` (1 = `Default`)
+    pub fn all() -> &'static [CropMode] {
+        &[
+            CropMode::Default,
+            CropMode::Split {
+                facecam: Corner::BottomLeft,
+            },
+            CropMode::Split {
+                facecam: Corner::BottomRight,
+            },
+            CropMode::Square,
+            CropMode::Split {
+                facecam: Corner::TopLeft,
+            },
+            CropMode::Split {
+                facecam: Corner::TopRight,
+            },
+            CropMode::MotionTrack { offset: None },
+        ]
+    }
+
+    /// Name, description and encoding cost of this mode
+    pub fn info(&self) -> CropModeInfo {
+        CropModeInfo {
+            id: self.to_string(),
+            description: self.description().to_string(),
+            gpu_recommended: self.is_complex_filter(),
+        }
+    }
+
+    /// Default output frame size for this crop mode
+    pub fn default_output_spec(&self) -> OutputSpec {
+        match self {
+            CropMode::Square => OutputSpec::square(SQUARE_SIZE),
+            _ => OutputSpec::default(),
+        }
+    }
+
+    /// Get the FFmpeg video filter string for this crop mode at its default output size
     pub fn get_ffmpeg_filter(&self) -> String {
+        self.ffmpeg_filter_for(&self.default_output_spec())
+    }
+
+    /// Get the FFmpeg video filter string for this crop mode at the given output size
+    pub fn ffmpeg_filter_for(&self, spec: &OutputSpec) -> String {
+        self.ffmpeg_filter_with_layout(spec, &SplitLayout::default())
+    }
+
+    /// Size of the frames this mode produces for `spec` (square mode uses the shorter side)
+    pub fn frame_size(&self, spec: &OutputSpec) -> OutputSpec {
         match self {
+            CropMode::Square => OutputSpec::square(spec.width.min(spec.height)),
+            _ => *spec,
+        }
+    }
+
+    /// Get the FFmpeg video filter string at the given output size, with split modes
+    /// arranged according to `layout`
+    pub fn ffmpeg_filter_with_layout(&self, spec: &OutputSpec, layout: &SplitLayout) -> String {
+        match self {
+            CropMode::Default if layout.fit == FitMode::Pad => {
+                // Scale to fit inside the target, then center it on black bars
+                format!(
+                    "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:black,setsar=1",
+                    w = spec.width,
+                    h = spec.height
+                )
+            }
             CropMode::Default => {
-                // Scale to cover 720x1280 (maintains aspect ratio, ensures both dimensions are >= target)
-                // Then center crop to exactly 720x1280
+                // Scale to cover the target (maintains aspect ratio, ensures both dimensions are >= target)
+                // Then center crop to exactly the target size
+                // setsar=1 keeps square pixels so clips can be concatenated without re-encoding
+                // Zooming in covers a proportionally larger target, so the crop keeps less
+                let zoom = if layout.zoom.is_finite() { layout.zoom.clamp(MIN_ZOOM, 1.0) } else { 1.0 };
+                format!(
+                    "scale={}:{}:force_original_aspect_ratio=increase,crop={}:{},setsar=1",
+                    even(spec.width as f64 / zoom),
+                    even(spec.height as f64 / zoom),
+                    spec.width,
+                    spec.height
+                )
+            }
+            CropMode::Square => {
+                // Same as default but with equal sides, using the shorter side of the spec
+                let side = self.frame_size(spec).width;
+                format!(
+                    "scale={}:{}:force_original_aspect_ratio=increase,crop={}:{},setsar=1",
+                    side, side, side, side
+                )
+            }
+            CropMode::MotionTrack { offset } => {
+                // Cut a window of the target's aspect ratio out of the full-height source,
+                // shifted by the offset and clamped to the frame, then scale it like the
+                // default mode (sources narrower than the target keep their full width)
                 format!(
-                    "scale={}:{}:force_original_aspect_ratio=increase,crop={}:{}",
-                    OUTPUT_WIDTH, OUTPUT_HEIGHT, OUTPUT_WIDTH, OUTPUT_HEIGHT
+                    "crop=w='min(iw,trunc(ih*{w}/{h}/2)*2)':h=ih:x='clip((iw-ow)/2+({offset}),0,iw-ow)':y=0,\
+                    scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},setsar=1",
+                    w = spec.width,
+                    h = spec.height,
+                    offset = offset.unwrap_or(0)
                 )
             }
-            CropMode::SplitLeft => {
-                // Split crop: top = center of video, bottom = bottom-left corner (facecam)
+            CropMode::Split { facecam } => {
+                // Split crop: top = center of video, bottom = facecam corner
                 //
                 // Strategy:
                 // 1. Scale video to fixed height (1280) to ensure we have enough pixels
                 // 2. Split the SCALED video (before any cropping)
-                // 3. Crop center region for top section (720x960)
-                // 4. Crop bottom-left corner for facecam (720x350)
+                // 3. Crop center region for top section (720x960 at the default ratio)
+                // 4. Crop the facecam corner (720x320, the remaining height)
                 // 5. Stack vertically
                 //
                 // For a 16:9 video scaled to height 1280:
                 //   - Width becomes ~2276
                 //   - Top crop: center of video (x=(2276-720)/2, y=(1280-960)/2)
-                //   - Bottom crop: bottom-left (x=0, y=1280-350=930)
-                format!(
-                    "scale=-2:{}[scaled];\
-                    [scaled]split=2[s1][s2];\
-                    [s1]crop={}:{}:(iw-{})/2:(ih-{})/2[top];\
-                    [s2]crop={}:{}:0:ih-{}[bottom];\
-                    [top][bottom]vstack=inputs=2[out]",
-                    OUTPUT_HEIGHT,  // Scale to height 1280
-                    OUTPUT_WIDTH, TOP_HEIGHT, OUTPUT_WIDTH, TOP_HEIGHT,  // Center crop 720x960
-                    OUTPUT_WIDTH, BOTTOM_HEIGHT, BOTTOM_HEIGHT  // Bottom-left crop 720x350
-                )
-            }
-            CropMode::SplitRight => {
-                // Split crop: top = center of video, bottom = bottom-right corner (facecam)
-                //
-                // Same as SplitLeft but facecam from bottom-right instead
+                //   - Bottom crop: e.g. bottom-left (x=0, y=1280-320=960)
+                let (top_height, bottom_height) = layout.section_heights(spec.height);
+
+                // Facecam corner region, shrunk by the zoom factor and scaled back up to the slot
+                let zoom = layout.facecam_zoom.max(1.0);
+                let (cam_width, cam_height) = (
+                    even(spec.width as f64 / zoom),
+                    even(bottom_height as f64 / zoom),
+                );
+                let cam_scale = if cam_width == spec.width && cam_height == bottom_height {
+                    String::new()
+                } else {
+                    format!(",scale={}:{}", spec.width, bottom_height)
+                };
+
                 format!(
                     "scale=-2:{}[scaled];\
                     [scaled]split=2[s1][s2];\
                     [s1]crop={}:{}:(iw-{})/2:(ih-{})/2[top];\
-                    [s2]crop={}:{}:iw-{}:ih-{}[bottom];\
-                    [top][bottom]vstack=inputs=2[out]",
-                    OUTPUT_HEIGHT,  // Scale to height 1280
-                    OUTPUT_WIDTH, TOP_HEIGHT, OUTPUT_WIDTH, TOP_HEIGHT,  // Center crop 720x960
-                    OUTPUT_WIDTH, BOTTOM_HEIGHT, OUTPUT_WIDTH, BOTTOM_HEIGHT  // Bottom-right crop 720x350
+                    [s2]crop={}:{}:{}:{}{}[bottom];\
+                    [top][bottom]vstack=inputs=2,setsar=1[out]",
+                    spec.height,  // Scale to output height (1280)
+                    spec.width, top_height, spec.width, top_height,  // Center crop 720x960
+                    cam_width, cam_height, facecam.crop_x(cam_width), facecam.crop_y(cam_height), cam_scale  // Corner crop 720x320
                 )
             }
         }
@@ -82,30 +407,77 @@ impl CropMode {
 
     /// Check if this mode uses complex filter (requires -filter_complex instead of -vf)
     pub fn is_complex_filter(&self) -> bool {
-        matches!(self, CropMode::SplitLeft | CropMode::SplitRight)
+        matches!(self, CropMode::Split { .. })
     }
 
     /// Get human-readable description
     pub fn description(&self) -> &'static str {
         match self {
             CropMode::Default => "Default (center crop)",
-            CropMode::SplitLeft => "Split (top: center, bottom: bottom-left facecam)",
-            CropMode::SplitRight => "Split (top: center, bottom: bottom-right facecam)",
+            CropMode::Split { facecam } => match facecam {
+                Corner::BottomLeft => "Split (top: center, bottom: bottom-left facecam)",
+                Corner::BottomRight => "Split (top: center, bottom: bottom-right facecam)",
+                Corner::TopLeft => "Split (top: center, bottom: top-left facecam)",
+                Corner::TopRight => "Split (top: center, bottom: top-right facecam)",
+            },
+            CropMode::Square => "Square (1:1 center crop)",
+            CropMode::MotionTrack { .. } => "Motion track (crop follows the action)",
         }
     }
 
-    /// Parse from user input (1, 2, 3 or string names)
+    /// Where subtitles go unless the user picked a position.
+    ///
+    /// Split modes put the facecam at the bottom, so captions sit in the middle of the
+    /// gameplay section instead of over the face.
+    pub fn subtitle_position(&self) -> SubtitlePosition {
+        match self {
+            CropMode::Split { .. } => SubtitlePosition::Center,
+            CropMode::Default | CropMode::Square | CropMode::MotionTrack { .. } => SubtitlePosition::Bottom,
+        }
+    }
+
+    /// Parse from user input (1-7 or string names)
     pub fn from_input(input: &str) -> Option<Self> {
-        match input.trim().to_lowercase().as_str() {
+        let split = |facecam| Some(CropMode::Split { facecam });
+        match input.trim().to_lowercase().replace('_', "-").as_str() {
             "1" | "default" => Some(CropMode::Default),
-            "2" | "split-left" | "split_left" | "splitleft" => Some(CropMode::SplitLeft),
-            "3" | "split-right" | "split_right" | "splitright" => Some(CropMode::SplitRight),
+            "2" | "split-left" | "splitleft" | "split-bottom-left" => split(Corner::BottomLeft),
+            "3" | "split-right" | "splitright" | "split-bottom-right" => split(Corner::BottomRight),
+            "4" | "square" => Some(CropMode::Square),
+            "5" | "split-top-left" => split(Corner::TopLeft),
+            "6" | "split-top-right" => split(Corner::TopRight),
+            "7" | "motion-track" | "motion" => Some(CropMode::MotionTrack { offset: None }),
             _ => None,
         }
     }
 }
 
+/// Horizontal offset of the moving content from the frame's center, in source pixels.
+///
+/// `stderr` is the log of an ffmpeg run with `cropdetect=mode=mvedges` (see
+/// `build_motion_detect_command`): the source width comes from the input stream line and
+/// the content from the median center of the detected windows, so a few frames of camera
+/// shake don't drag the crop. `None` when nothing was detected.
+pub fn parse_content_offset(stderr: &str) -> Option<i32> {
+    let size = Regex::new(r"Stream #\S+.*Video: .*?, (\d{2,5})x(\d{2,5})").ok()?;
+    let window = Regex::new(r"crop=(\d+):(\d+):(\d+):(\d+)").ok()?;
+
+    let source_width: i64 = size.captures(stderr)?[1].parse().ok()?;
+    let mut centers: Vec<i64> = window
+        .captures_iter(stderr)
+        .filter_map(|c| Some(c[3].parse::<i64>().ok()? * 2 + c[1].parse::<i64>().ok()?))
+        .collect();
+    if centers.is_empty() {
+        return None;
+    }
+    centers.sort_unstable();
+    // Centers are kept doubled so odd widths don't round
+    let median = centers[centers.len() / 2];
+    Some(((median - source_width) / 2) as i32)
+}
+
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
 
@@ -119,10 +491,207 @@ mod tests {
         assert_eq!(CropMode::from_input("invalid"), None);
     }
 
+    #[test]
+    fn test_all_crop_modes() {
+        // Numbered like the interactive prompt, and every name parses back to its mode
+        for (number, mode) in CropMode::all().iter().enumerate() {
+            assert_eq!(CropMode::from_input(&(number + 1).to_string()), Some(*mode));
+            assert_eq!(CropMode::from_input(&mode.to_string()), Some(*mode));
+        }
+        assert_eq!(CropMode::all().len(), 7);
+
+        let info = CropMode::SplitLeft.info();
+        assert_eq!(info.id, "split-left");
+        assert!(info.gpu_recommended);
+        assert!(!CropMode::Square.info().gpu_recommended);
+    }
+
     #[test]
     fn test_is_complex_filter() {
         assert!(!CropMode::Default.is_complex_filter());
         assert!(CropMode::SplitLeft.is_complex_filter());
         assert!(CropMode::SplitRight.is_complex_filter());
+        assert!(!CropMode::Square.is_complex_filter());
+    }
+
+    #[test]
+    fn test_square_filter() {
+        assert_eq!(CropMode::from_input("square"), Some(CropMode::Square));
+        assert_eq!(
+            CropMode::Square.get_ffmpeg_filter(),
+            "scale=1080:1080:force_original_aspect_ratio=increase,crop=1080:1080,setsar=1"
+        );
+        assert_eq!(
+            CropMode::Square.ffmpeg_filter_for(&OutputSpec::square(720)),
+            "scale=720:720:force_original_aspect_ratio=increase,crop=720:720,setsar=1"
+        );
+    }
+
+    #[test]
+    fn test_motion_track_filter() {
+        let tracked = CropMode::MotionTrack { offset: Some(-240) };
+        assert_eq!(CropMode::from_input("motion-track"), Some(CropMode::MotionTrack { offset: None }));
+        assert_eq!(tracked.to_string(), "motion-track");
+        assert!(!tracked.is_complex_filter());
+        assert_eq!(
+            tracked.get_ffmpeg_filter(),
+            "crop=w='min(iw,trunc(ih*720/1280/2)*2)':h=ih:x='clip((iw-ow)/2+(-240),0,iw-ow)':y=0,\
+             scale=720:1280:force_original_aspect_ratio=increase,crop=720:1280,setsar=1"
+        );
+        assert!(CropMode::MotionTrack { offset: None }
+            .get_ffmpeg_filter()
+            .contains("x='clip((iw-ow)/2+(0),0,iw-ow)'"));
+    }
+
+    #[test]
+    fn test_parse_content_offset() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'in.mp4':\n  \
+            Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(tv, bt709, progressive), 1920x1080 [SAR 1:1 DAR 16:9], 4000 kb/s, 30 fps\n\
+            [Parsed_cropdetect_0 @ 0x5599] x1:1200 x2:1599 y1:0 y2:1079 w:400 h:1072 x:1200 y:4 pts:1 t:0.03 limit:0.09 crop=400:1072:1200:4\n\
+            [Parsed_cropdetect_0 @ 0x5599] x1:1100 x2:1599 y1:0 y2:1079 w:500 h:1072 x:1100 y:4 pts:2 t:0.06 limit:0.09 crop=500:1072:1100:4\n\
+            [Parsed_cropdetect_0 @ 0x5599] x1:0 x2:1919 y1:0 y2:1079 w:1920 h:1072 x:0 y:4 pts:3 t:0.1 limit:0.09 crop=1920:1072:0:4\n";
+        // Median window is 500 wide at x=1100: centered at 1350, 390 right of 960
+        assert_eq!(parse_content_offset(stderr), Some(390));
+        assert_eq!(parse_content_offset("Stream #0:0: Video: h264, 1920x1080"), None);
+    }
+
+    #[test]
+    fn test_facecam_zoom_adds_scale() {
+        let spec = OutputSpec::default();
+        let plain = CropMode::SplitLeft.get_ffmpeg_filter();
+        assert!(plain.contains("[s2]crop=720:320:0:ih-320[bottom]"));
+        assert_eq!(
+            CropMode::SplitLeft.ffmpeg_filter_with_layout(&spec, &SplitLayout::default()),
+            plain
+        );
+
+        let zoomed = SplitLayout {
+            facecam_zoom: 2.0,
+            ..SplitLayout::default()
+        };
+        let left = CropMode::SplitLeft.ffmpeg_filter_with_layout(&spec, &zoomed);
+        assert!(left.contains("[s2]crop=360:160:0:ih-160,scale=720:320[bottom]"));
+        let right = CropMode::SplitRight.ffmpeg_filter_with_layout(&spec, &zoomed);
+        assert!(right.contains("[s2]crop=360:160:iw-360:ih-160,scale=720:320[bottom]"));
+    }
+
+    #[test]
+    fn test_default_mode_fit() {
+        let spec = OutputSpec::default();
+        let fill = SplitLayout {
+            fit: FitMode::Fill,
+            ..SplitLayout::default()
+        };
+        assert_eq!(
+            CropMode::Default.ffmpeg_filter_with_layout(&spec, &fill),
+            "scale=720:1280:force_original_aspect_ratio=increase,crop=720:1280,setsar=1"
+        );
+
+        let pad = SplitLayout {
+            fit: FitMode::Pad,
+            ..SplitLayout::default()
+        };
+        assert_eq!(
+            CropMode::Default.ffmpeg_filter_with_layout(&spec, &pad),
+            "scale=720:1280:force_original_aspect_ratio=decrease,pad=720:1280:(ow-iw)/2:(oh-ih)/2:black,setsar=1"
+        );
+        // Only the default mode letterboxes
+        assert_eq!(
+            CropMode::Square.ffmpeg_filter_with_layout(&spec, &pad),
+            CropMode::Square.ffmpeg_filter_for(&spec)
+        );
+        assert_eq!(FitMode::from_input("Letterbox"), Some(FitMode::Pad));
+        assert_eq!(FitMode::from_input("stretch"), None);
+    }
+
+    #[test]
+    fn test_default_mode_zoom() {
+        let spec = OutputSpec::default();
+        assert_eq!(
+            CropMode::Default.get_ffmpeg_filter(),
+            "scale=720:1280:force_original_aspect_ratio=increase,crop=720:1280,setsar=1"
+        );
+
+        let zoomed = SplitLayout {
+            zoom: 0.5,
+            ..SplitLayout::default()
+        };
+        assert_eq!(
+            CropMode::Default.ffmpeg_filter_with_layout(&spec, &zoomed),
+            "scale=1440:2560:force_original_aspect_ratio=increase,crop=720:1280,setsar=1"
+        );
+
+        // Zooming out would leave the crop larger than the scaled frame
+        let out = SplitLayout {
+            zoom: 2.0,
+            ..SplitLayout::default()
+        };
+        assert_eq!(
+            CropMode::Default.ffmpeg_filter_with_layout(&spec, &out),
+            CropMode::Default.get_ffmpeg_filter()
+        );
+    }
+
+    #[test]
+    fn test_split_sections_fill_height() {
+        for height in [1280, 1920, 1080] {
+            for ratio in [0.5, 0.6, 0.75, 0.8, 0.333, 0.0, 1.0] {
+                let layout = SplitLayout {
+                    split_ratio: ratio,
+                    ..SplitLayout::default()
+                };
+                let (top, bottom) = layout.section_heights(height);
+                assert_eq!(top + bottom, height, "ratio {ratio} at {height}");
+                assert!(top >= 2 && bottom >= 2);
+                assert_eq!(top % 2, 0);
+            }
+        }
+
+        let filter = CropMode::SplitRight.get_ffmpeg_filter();
+        assert!(filter.contains("[s1]crop=720:960:(iw-720)/2:(ih-960)/2[top]"));
+        assert!(filter.contains("[s2]crop=720:320:iw-720:ih-320[bottom]"));
+    }
+
+    #[test]
+    fn test_corner_crop_offsets() {
+        let corner = |facecam| {
+            let filter = CropMode::Split { facecam }.get_ffmpeg_filter();
+            let start = filter.find("[s2]crop=").unwrap();
+            let end = filter.find("[bottom]").unwrap();
+            filter[start + 4..end].to_string()
+        };
+        assert_eq!(corner(Corner::BottomLeft), "crop=720:320:0:ih-320");
+        assert_eq!(corner(Corner::BottomRight), "crop=720:320:iw-720:ih-320");
+        assert_eq!(corner(Corner::TopLeft), "crop=720:320:0:0");
+        assert_eq!(corner(Corner::TopRight), "crop=720:320:iw-720:0");
+    }
+
+    #[test]
+    fn test_split_aliases_round_trip() {
+        assert_eq!(
+            CropMode::SplitLeft,
+            CropMode::Split {
+                facecam: Corner::BottomLeft
+            }
+        );
+        assert_eq!(CropMode::SplitRight.to_string(), "split-right");
+        let top_right = CropMode::Split {
+            facecam: Corner::TopRight,
+        };
+        assert_eq!(top_right.to_string(), "split-top-right");
+        assert_eq!(CropMode::from_input("split_top_right"), Some(top_right));
+
+        assert_eq!(serde_json::to_string(&CropMode::SplitLeft).unwrap(), "\"split-left\"");
+        let parsed: CropMode = serde_json::from_str("\"split-right\"").unwrap();
+        assert_eq!(parsed, CropMode::SplitRight);
+        assert!(serde_json::from_str::<CropMode>("\"sideways\"").is_err());
+    }
+
+    #[test]
+    fn test_output_spec_parse() {
+        assert_eq!(OutputSpec::parse("1080x1920"), Some(OutputSpec::new(1080, 1920)));
+        assert_eq!(OutputSpec::parse("720"), Some(OutputSpec::square(720)));
+        assert_eq!(OutputSpec::parse("721x1280"), None);
+        assert_eq!(OutputSpec::parse("abc"), None);
     }
 }
@@ -0,0 +1,289 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Environment variable naming the ffmpeg binary when no explicit path is given
+pub const FFMPEG_BIN_ENV: &str = "FFMPEG_BIN";
+
+/// Environment variable naming the yt-dlp binary when no explicit path is given
+pub const YTDLP_BIN_ENV: &str = "YTDLP_BIN";
+
+/// Oldest FFmpeg release known to work (major, minor)
+pub const MIN_FFMPEG_VERSION: (u32, u32) = (4, 0);
+
+/// Oldest yt-dlp release known to handle current YouTube pages (year, month, day).
+/// yt-dlp versions are release dates; older builds routinely fail on YouTube changes.
+pub const MIN_YTDLP_VERSION: (u32, u32, u32) = (2024, 1, 1);
+
+/// Versions and capabilities of the external tools
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyVersions {
+    /// FFmpeg version string as reported by `ffmpeg -version`
+    pub ffmpeg: Option<String>,
+    /// yt-dlp version string as reported by `yt-dlp --version`
+    pub ytdlp: Option<String>,
+    /// Whether FFmpeg was built with the NVENC H.264 encoder
+    pub nvenc: bool,
+    /// Problems found with the detected versions
+    pub warnings: Vec<String>,
+}
+
+/// Extract the version token from `ffmpeg -version` output
+/// (e.g. `6.1.1-3ubuntu5` from `ffmpeg version 6.1.1-3ubuntu5 Copyright ...`)
+pub fn parse_ffmpeg_version(output: &str) -> Option<String> {
+    let line = output.lines().next()?;
+    let rest = line.trim().strip_prefix("ffmpeg version ")?;
+    rest.split_whitespace().next().map(|v| v.to_string())
+}
+
+/// Major and minor number of an FFmpeg version token.
+///
+/// Returns `None` for git snapshot builds (`N-112345-g...`) that carry no release number.
+pub fn ffmpeg_release(version: &str) -> Option<(u32, u32)> {
+    let version = version.trim_start_matches('n');
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|m| m.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+/// Release date of a yt-dlp version string (e.g. `2024.08.06` or `2024.08.06.232711` nightly)
+pub fn ytdlp_release(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    Some((year, month, day))
+}
+
+/// Compare detected versions against the known-good minimums
+pub fn version_warnings(ffmpeg: Option<&str>, ytdlp: Option<&str>) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if let Some(release) = ffmpeg.and_then(ffmpeg_release) {
+        if release < MIN_FFMPEG_VERSION {
+            warnings.push(format!(
+                "FFmpeg {}.{} is older than the minimum supported {}.{}",
+                release.0, release.1, MIN_FFMPEG_VERSION.0, MIN_FFMPEG_VERSION.1
+            ));
+        }
+    }
+
+    if let Some(release) = ytdlp.and_then(ytdlp_release) {
+        if release < MIN_YTDLP_VERSION {
+            warnings.push(format!(
+                "yt-dlp {} is older than {}.{:02}.{:02} and may fail on current YouTube pages (run with --update)",
+                ytdlp.unwrap_or_default(),
+                MIN_YTDLP_VERSION.0,
+                MIN_YTDLP_VERSION.1,
+                MIN_YTDLP_VERSION.2
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// `explicit` if given, else the path in the `env` variable, else `name` (looked up on PATH)
+fn resolve_program(explicit: Option<&Path>, env: &str, name: &str) -> PathBuf {
+    explicit
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(env).filter(|v| !v.is_empty()).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// The ffmpeg binary to run: `explicit`, `$FFMPEG_BIN`, or `ffmpeg` from PATH
+pub fn ffmpeg_program(explicit: Option<&Path>) -> PathBuf {
+    resolve_program(explicit, FFMPEG_BIN_ENV, "ffmpeg")
+}
+
+/// The yt-dlp binary to run: `explicit`, `$YTDLP_BIN`, or `yt-dlp` from PATH
+pub fn ytdlp_program(explicit: Option<&Path>) -> PathBuf {
+    resolve_program(explicit, YTDLP_BIN_ENV, "yt-dlp")
+}
+
+/// The ffprobe binary that ships with the resolved ffmpeg: next to an explicit ffmpeg
+/// path (keeping its `.exe` extension), otherwise `ffprobe` from PATH
+pub fn ffprobe_program(ffmpeg_path: Option<&Path>) -> PathBuf {
+    let ffmpeg = ffmpeg_program(ffmpeg_path);
+    match ffmpeg.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => {
+            let mut ffprobe = dir.join("ffprobe");
+            if let Some(ext) = ffmpeg.extension() {
+                ffprobe.set_extension(ext);
+            }
+            ffprobe
+        }
+        None => PathBuf::from("ffprobe"),
+    }
+}
+
+fn command_stdout(program: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        None
+    }
+}
+
+/// Run the tools to detect their versions and capabilities (see `ffmpeg_program` and
+/// `ytdlp_program` for how the binaries are found)
+pub fn detect_versions(ffmpeg_path: Option<&Path>, ytdlp_path: Option<&Path>) -> DependencyVersions {
+    let ffmpeg_bin = ffmpeg_program(ffmpeg_path);
+    let ffmpeg = command_stdout(&ffmpeg_bin, &["-version"]).and_then(|o| parse_ffmpeg_version(&o));
+    let ytdlp = command_stdout(&ytdlp_program(ytdlp_path), &["--version"])
+        .map(|o| o.trim().to_string())
+        .filter(|v| !v.is_empty());
+    let nvenc = command_stdout(&ffmpeg_bin, &["-hide_banner", "-encoders"])
+        .map(|o| o.contains("h264_nvenc"))
+        .unwrap_or(false);
+    let warnings = version_warnings(ffmpeg.as_deref(), ytdlp.as_deref());
+
+    DependencyVersions {
+        ffmpeg,
+        ytdlp,
+        nvenc,
+        warnings,
+    }
+}
+
+/// Error unless `program` can be run: an explicit path must point at an executable,
+/// a bare `name` must be on PATH (otherwise the error is `hint`)
+fn ensure_tool(program: &Path, name: &str, env: &str, hint: &str) -> Result<()> {
+    if which::which(program).is_ok() {
+        return Ok(());
+    }
+    if program != Path::new(name) {
+        return Err(anyhow!(
+            "{} not found at {} (set with --{}-path or {})",
+            name,
+            program.display(),
+            name.replace('-', ""),
+            env
+        ));
+    }
+    Err(anyhow!("{}", hint))
+}
+
+/// FFmpeg is needed by every command that touches video
+pub fn ensure_ffmpeg(ffmpeg_path: Option<&Path>) -> Result<()> {
+    ensure_tool(
+        &ffmpeg_program(ffmpeg_path),
+        "ffmpeg",
+        FFMPEG_BIN_ENV,
+        "FFmpeg not found. Please install FFmpeg and ensure it is in PATH, \
+         or point --ffmpeg-path (or FFMPEG_BIN) at it.",
+    )
+}
+
+/// yt-dlp is only needed to download from YouTube
+pub fn ensure_ytdlp(ytdlp_path: Option<&Path>) -> Result<()> {
+    ensure_tool(
+        &ytdlp_program(ytdlp_path),
+        "yt-dlp",
+        YTDLP_BIN_ENV,
+        "yt-dlp not found. Please install it and ensure it is in PATH, \
+         or point --ytdlp-path (or YTDLP_BIN) at it.\n\
+         Download: https://github.com/yt-dlp/yt-dlp/releases",
+    )
+}
+
+/// Transcription needs whisper.cpp, or Python to run (and install) faster-whisper
+pub fn ensure_whisper() -> Result<()> {
+    if crate::subtitle::check_whisper_cpp_available() || crate::subtitle::check_python_available() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "No transcription backend found. Install whisper.cpp (whisper-cli) or Python 3 \
+         for faster-whisper."
+    ))
+}
+
+/// Check the full pipeline's dependencies (ffmpeg, yt-dlp) and their versions.
+///
+/// Explicit paths are validated as given instead of searching PATH.
+/// Outdated versions are printed as warnings, or returned as an error when `strict` is set.
+pub fn check_dependencies(
+    strict: bool,
+    ffmpeg_path: Option<&Path>,
+    ytdlp_path: Option<&Path>,
+) -> Result<DependencyVersions> {
+    ensure_ffmpeg(ffmpeg_path)?;
+    ensure_ytdlp(ytdlp_path)?;
+
+    let versions = detect_versions(ffmpeg_path, ytdlp_path);
+
+    if !versions.warnings.is_empty() {
+        if strict {
+            return Err(anyhow!(versions.warnings.join("\n")));
+        }
+        for warning in &versions.warnings {
+            tracing::warn!("{}", warning);
+        }
+    }
+
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ffmpeg_version() {
+        let out = "ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023 the FFmpeg developers\nbuilt with gcc";
+        assert_eq!(parse_ffmpeg_version(out), Some("6.1.1-3ubuntu5".to_string()));
+        assert_eq!(ffmpeg_release("6.1.1-3ubuntu5"), Some((6, 1)));
+        assert_eq!(ffmpeg_release("n7.0"), Some((7, 0)));
+        assert_eq!(ffmpeg_release("7.0.1-essentials_build-www.gyan.dev"), Some((7, 0)));
+        assert_eq!(ffmpeg_release("N-112345-gabcdef"), None);
+        assert_eq!(parse_ffmpeg_version("not ffmpeg"), None);
+    }
+
+    #[test]
+    fn test_resolve_program() {
+        let env = "YT_CLIPPER_TEST_BIN";
+        assert_eq!(resolve_program(None, env, "ffmpeg"), PathBuf::from("ffmpeg"));
+        std::env::set_var(env, "/opt/ffmpeg-7/bin/ffmpeg");
+        assert_eq!(
+            resolve_program(None, env, "ffmpeg"),
+            PathBuf::from("/opt/ffmpeg-7/bin/ffmpeg")
+        );
+        let explicit = Path::new("C:\\tools\\ffmpeg.exe");
+        assert_eq!(resolve_program(Some(explicit), env, "ffmpeg"), explicit);
+        std::env::remove_var(env);
+
+        assert_eq!(ffprobe_program(Some(Path::new("/opt/ffmpeg/ffmpeg"))), Path::new("/opt/ffmpeg/ffprobe"));
+        assert_eq!(ffprobe_program(Some(Path::new("tools/ffmpeg.exe"))), Path::new("tools/ffprobe.exe"));
+    }
+
+    #[test]
+    fn test_ytdlp_release() {
+        assert_eq!(ytdlp_release("2024.08.06"), Some((2024, 8, 6)));
+        assert_eq!(ytdlp_release("2024.08.06.232711"), Some((2024, 8, 6)));
+        assert_eq!(ytdlp_release("unknown"), None);
+    }
+
+    #[test]
+    fn test_ensure_tool_reports_hint() {
+        let name = "yt-clipper-missing-tool";
+        let err = ensure_tool(Path::new(name), name, "MISSING_BIN", "missing-tool not found").unwrap_err();
+        assert_eq!(err.to_string(), "missing-tool not found");
+
+        // An explicit path is reported as given instead of the install hint
+        let err = ensure_tool(Path::new("/nonexistent/ffmpeg"), "ffmpeg", FFMPEG_BIN_ENV, "hint")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("/nonexistent/ffmpeg") && err.contains("--ffmpeg-path"), "{}", err);
+    }
+
+    #[test]
+    fn test_version_warnings() {
+        assert!(version_warnings(Some("6.1"), Some("2024.08.06")).is_empty());
+        assert_eq!(version_warnings(Some("3.4.8"), Some("2021.12.01")).len(), 2);
+        // Unparseable versions are not flagged
+        assert!(version_warnings(Some("N-112345-gabcdef"), None).is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::process::Command;
+
+use crate::runner::CommandRunner;
+use crate::{HeatmapSegment, MIN_SCORE};
+
+/// Sample rate the audio is decoded at; loudness needs far less than speech or music
+pub const ENERGY_SAMPLE_RATE: u32 = 8000;
+/// Length of each scored window, in seconds
+pub const ENERGY_WINDOW_SECONDS: f64 = 5.0;
+
+/// Build the ffmpeg command that decodes the audio of `input` to mono 16-bit PCM on stdout
+pub fn build_decode_command(input: &Path, ffmpeg: &Path) -> Command {
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-v", "error", "-i"])
+        .arg(input)
+        .args(["-vn", "-ac", "1", "-ar", &ENERGY_SAMPLE_RATE.to_string()])
+        .args(["-f", "s16le", "-"]);
+    cmd
+}
+
+/// Little-endian 16-bit samples as printed by `build_decode_command`
+pub fn parse_samples(bytes: &[u8]) -> Vec<i16> {
+    bytes
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+/// Score every `window`-second stretch of `samples` by its mean power relative to the
+/// loudest one, like the replay heatmap's 0-1 intensity.
+///
+/// Returns the windows scoring at least `MIN_SCORE`, sorted by score descending. Silence
+/// yields nothing.
+pub fn loudest_segments(samples: &[i16], sample_rate: u32, window: f64) -> Vec<HeatmapSegment> {
+    let window_len = ((sample_rate as f64 * window) as usize).max(1);
+    let powers: Vec<f64> = samples
+        .chunks(window_len)
+        .map(|chunk| {
+            let sum: f64 = chunk.iter().map(|&sample| (sample as f64).powi(2)).sum();
+            sum / chunk.len() as f64
+        })
+        .collect();
+    let loudest = powers.iter().cloned().fold(0.0_f64, f64::max);
+    if loudest <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut segments: Vec<HeatmapSegment> = powers
+        .iter()
+        .enumerate()
+        .map(|(i, power)| {
+            let start = (i * window_len) as f64 / sample_rate as f64;
+            let end = ((i + 1) * window_len).min(samples.len()) as f64 / sample_rate as f64;
+            HeatmapSegment {
+                start,
+                duration: end - start,
+                score: power / loudest,
+            }
+        })
+        .filter(|segment| segment.score >= MIN_SCORE)
+        .collect();
+    segments.sort_by(|a, b| b.score.total_cmp(&a.score));
+    segments
+}
+
+/// Decode the audio of `input` and score its loudest moments (see `loudest_segments`)
+pub fn analyze_audio_energy(
+    input: &Path,
+    ffmpeg: &Path,
+    runner: &dyn CommandRunner,
+) -> Result<Vec<HeatmapSegment>> {
+    let output = runner.output(&mut build_decode_command(input, ffmpeg))?;
+    if !output.success {
+        return Err(anyhow!(
+            "ffmpeg could not decode the audio of {}: {}",
+            input.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let samples = parse_samples(&output.stdout);
+    Ok(loudest_segments(&samples, ENERGY_SAMPLE_RATE, ENERGY_WINDOW_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loudest_segments_of_synthetic_waveform() {
+        // 30s of quiet 440 Hz tone with a loud burst from 10s to 15s
+        let rate = 1000;
+        let samples: Vec<i16> = (0..30 * rate)
+            .map(|i| {
+                let t = i as f64 / rate as f64;
+                let amplitude = if (10.0..15.0).contains(&t) { 20000.0 } else { 2000.0 };
+                (amplitude * (2.0 * std::f64::consts::PI * 440.0 * t).sin()) as i16
+            })
+            .collect();
+
+        let segments = loudest_segments(&samples, rate as u32, 5.0);
+        assert_eq!(segments.len(), 1);
+        assert_eq!((segments[0].start, segments[0].duration), (10.0, 5.0));
+        assert_eq!(segments[0].score, 1.0);
+
+        assert!(loudest_segments(&vec![0; 5000], rate as u32, 5.0).is_empty());
+
+        let bytes: Vec<u8> = [1i16, -2].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        assert_eq!(parse_samples(&bytes), vec![1, -2]);
+    }
+}
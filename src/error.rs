@@ -0,0 +1,152 @@
+use std::fmt;
+
+/// Suggestion for videos that need a signed-in YouTube session
+const COOKIES_HINT: &str = "If your account can watch it, add `--cookies-from-browser <browser>` \
+     (e.g. chrome or firefox) to your yt-dlp config file so yt-dlp uses your login.";
+
+/// Videos that can't be clipped for a known reason, recognized from yt-dlp's stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipperError {
+    /// Uploader made the video private
+    PrivateVideo,
+    /// Removed by the uploader or YouTube, or the channel was terminated
+    VideoUnavailable,
+    /// Only channel members can watch it
+    MembersOnly,
+    /// Not available from this country
+    GeoBlocked,
+    /// YouTube wants a signed-in, age-verified account
+    AgeRestricted,
+}
+
+impl ClipperError {
+    /// Recognize yt-dlp's error message for an inaccessible video.
+    ///
+    /// Returns `None` for other failures (network errors, bad formats, ...).
+    pub fn from_ytdlp_stderr(stderr: &str) -> Option<Self> {
+        let stderr = stderr.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| stderr.contains(needle));
+
+        // Geo and members-only messages also start with "Video unavailable", so the
+        // generic check comes last
+        if has(&["private video"]) {
+            Some(ClipperError::PrivateVideo)
+        } else if has(&["members-only", "available to this channel's members"]) {
+            Some(ClipperError::MembersOnly)
+        } else if has(&["confirm your age", "age-restricted", "age restricted"]) {
+            Some(ClipperError::AgeRestricted)
+        } else if has(&["in your country", "geo restriction", "geo-restricted"]) {
+            Some(ClipperError::GeoBlocked)
+        } else if has(&[
+            "video unavailable",
+            "video is unavailable",
+            "has been removed",
+            "account associated with this video has been terminated",
+        ]) {
+            Some(ClipperError::VideoUnavailable)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ClipperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipperError::PrivateVideo => write!(f, "This video is private. {}", COOKIES_HINT),
+            ClipperError::VideoUnavailable => write!(
+                f,
+                "This video is unavailable (removed, or its channel was terminated). Check the URL."
+            ),
+            ClipperError::MembersOnly => write!(
+                f,
+                "This video is for channel members only. {}",
+                COOKIES_HINT
+            ),
+            ClipperError::GeoBlocked => write!(
+                f,
+                "This video is not available in your country. Try --proxy with a server in a \
+                 country where it is available."
+            ),
+            ClipperError::AgeRestricted => write!(
+                f,
+                "This video is age-restricted and needs a signed-in account. {}",
+                COOKIES_HINT
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ClipperError {}
+
+/// The job was cancelled while it was running (see `runner::CancellableRunner`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Job cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// The watch page has no "Most replayed" data (new, short or unpopular videos)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoHeatmap;
+
+impl fmt::Display for NoHeatmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No heatmap markers found")
+    }
+}
+
+impl std::error::Error for NoHeatmap {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ytdlp_stderr() {
+        let cases = [
+            (
+                "ERROR: [youtube] abc123def45: Private video. Sign in if you've been granted access to this video",
+                Some(ClipperError::PrivateVideo),
+            ),
+            (
+                "ERROR: [youtube] abc123def45: Video unavailable. This video has been removed by the uploader",
+                Some(ClipperError::VideoUnavailable),
+            ),
+            (
+                "ERROR: [youtube] abc123def45: Video unavailable. This video is no longer available because \
+                 the YouTube account associated with this video has been terminated.",
+                Some(ClipperError::VideoUnavailable),
+            ),
+            (
+                "ERROR: [youtube] abc123def45: Join this channel to get access to members-only content like \
+                 this video, and other exclusive perks.",
+                Some(ClipperError::MembersOnly),
+            ),
+            (
+                "ERROR: [youtube] abc123def45: Video unavailable. The uploader has not made this video \
+                 available in your country",
+                Some(ClipperError::GeoBlocked),
+            ),
+            (
+                "ERROR: [youtube] abc123def45: Sign in to confirm your age. This video may be inappropriate \
+                 for some users. Use --cookies-from-browser or --cookies for the authentication.",
+                Some(ClipperError::AgeRestricted),
+            ),
+            (
+                "ERROR: [youtube] abc123def45: Unable to download API page: HTTP Error 503",
+                None,
+            ),
+        ];
+
+        for (stderr, expected) in cases {
+            assert_eq!(ClipperError::from_ytdlp_stderr(stderr), expected, "{}", stderr);
+        }
+        assert!(ClipperError::PrivateVideo.to_string().contains("--cookies-from-browser"));
+    }
+}
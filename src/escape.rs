@@ -0,0 +1,119 @@
+/// Escape a file path for use as a filter option value inside an FFmpeg filtergraph
+/// (e.g. `ass=<path>` or `subtitles=<path>`).
+///
+/// FFmpeg unescapes filter arguments twice: once when parsing the filtergraph and once
+/// when parsing the filter's options, so special characters are escaped for both levels.
+/// The result must be used unquoted. Windows backslashes are normalized to `/` first.
+pub fn escape_filter_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+
+    // Level 1: filter option value (key=value:key=value)
+    let mut level1 = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '\\' | ':' | '\'') {
+            level1.push('\\');
+        }
+        level1.push(c);
+    }
+
+    escape_filtergraph(&level1)
+}
+
+/// Escape text for the `text=` option of FFmpeg's `drawtext` filter, for use unquoted
+/// inside a filtergraph. Use it with `expansion=none` so `%` is printed literally.
+pub fn escape_drawtext_text(text: &str) -> String {
+    // Level 1: filter option value
+    let mut level1 = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ':' | '\'' => {
+                level1.push('\\');
+                level1.push(c);
+            }
+            '\n' | '\r' => level1.push(' '),
+            _ => level1.push(c),
+        }
+    }
+
+    escape_filtergraph(&level1)
+}
+
+/// Level 2 escaping: filtergraph description (filter,filter;[label])
+fn escape_filtergraph(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escape text for an ASS `Dialogue` line so it is rendered literally.
+///
+/// Braces would otherwise start override blocks and `\N`/`\n`/`\h` would be interpreted as
+/// line breaks or hard spaces. A literal backslash cannot be expressed in ASS, so it is
+/// replaced with the visually similar U+29F5 (reverse solidus operator).
+pub fn escape_ass_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push('\u{29F5}'),
+            '{' => escaped.push_str("\\{"),
+            '}' => escaped.push_str("\\}"),
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape a value for an FFmpeg metadata file (`;FFMETADATA1`), where `=`, `;`, `#`, `\`
+/// and newlines are special.
+pub fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_filter_path() {
+        assert_eq!(escape_filter_path("/tmp/clip.ass"), "/tmp/clip.ass");
+        assert_eq!(escape_filter_path("C:\\clips\\a.ass"), "C\\\\:/clips/a.ass");
+        assert_eq!(
+            escape_filter_path("/tmp/O'Brien: \"Live!\".ass"),
+            "/tmp/O\\\\\\'Brien\\\\: \"Live!\".ass"
+        );
+        assert_eq!(escape_filter_path("a,b;[c].ass"), "a\\,b\\;\\[c\\].ass");
+    }
+
+    #[test]
+    fn test_escape_drawtext_text() {
+        assert_eq!(escape_drawtext_text("PREVIEW"), "PREVIEW");
+        assert_eq!(escape_drawtext_text("a:b, c"), r"a\\:b\, c");
+        assert_eq!(escape_drawtext_text("it's"), r"it\\\'s");
+    }
+
+    #[test]
+    fn test_escape_ass_text() {
+        assert_eq!(escape_ass_text("O'Brien: \"Live!\""), "O'Brien: \"Live!\"");
+        assert_eq!(escape_ass_text("{\\b1}bold"), "\\{\u{29F5}b1\\}bold");
+        assert_eq!(escape_ass_text("line\\Nbreak"), "line\u{29F5}Nbreak");
+    }
+
+    #[test]
+    fn test_escape_ffmetadata() {
+        assert_eq!(escape_ffmetadata("#1 @ 1:05"), "\\#1 @ 1:05");
+        assert_eq!(escape_ffmetadata("a=b;c\\d"), "a\\=b\\;c\\\\d");
+    }
+}
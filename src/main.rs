@@ -1,8 +1,16 @@
+use anyhow::anyhow;
 use clap::Parser;
 use std::io::{self, Write};
+use tracing_subscriber::EnvFilter;
 use yt_clipper_rust::{
-    check_dependencies, full_process, update_ytdlp,
-    CropMode, ProcessOptions, SubtitleConfig, WhisperModel,
+    check_dependencies, deps, expand_playlist, extract_playlist_id, full_process, overlay, selection,
+    subtitle::{
+        self, download_whisper_model, parse_language, HighlightAnimation, SubtitleFormat, SubtitleMode,
+        SubtitlePosition, TranscriptCache, WhisperTask,
+    },
+    update_ytdlp,
+    AudioCodec, AudioFormat, CropMode, FitMode, OutputOrder, OutputSpec, OverlayPosition, OverlaySpec, PreviewFormat, ProcessOptions, ProcessResult, RunOutcome, ScoreWeighting, SegmentSource, SelectionStrategy, SubtitleConfig, SubtitlePreset,
+    WhisperModel,
 };
 
 mod server;
@@ -11,6 +19,8 @@ mod server;
 #[command(name = "yt-clipper-rust")]
 #[command(version, about = "YouTube Heatmap Clipper - Generate viral vertical clips from YouTube videos")]
 #[command(long_about = "Automatically extract high-engagement moments from YouTube videos using heatmap data and convert them to vertical format for social media.")]
+#[command(after_help = "Exit codes: 0 = every requested clip was made, 2 = partial success (some clips, \
+    subtitles or the reel failed), 1 = nothing was made or the run failed.")]
 struct Args {
     /// Run in web server mode
     #[arg(long)]
@@ -20,34 +30,185 @@ struct Args {
     #[arg(long, default_value_t = 3000)]
     port: u16,
 
-    /// YouTube URL (optional, will prompt if not provided)
+    /// Maximum number of jobs the server processes at once
+    #[arg(long, default_value_t = 2)]
+    max_jobs: usize,
+
+    /// Maximum /api/process requests per client IP per minute
+    #[arg(long, default_value_t = 10)]
+    rate_limit: u32,
+
+    /// YouTube video or playlist URL, or a local video file (optional, will prompt if not provided)
     #[arg(short, long)]
     url: Option<String>,
 
-    /// Crop mode: default, split-left, split-right
+    /// Crop mode: default, split-left, split-right, split-top-left, split-top-right, square,
+    /// motion-track
     #[arg(short, long, default_value = "default")]
     crop: String,
 
+    /// Facecam zoom for split modes (e.g. 1.5 crops a smaller corner and scales it up)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    facecam_zoom: f64,
+
+    /// Default crop mode: share of the source height kept (1.0 = fill the frame, e.g. 0.8
+    /// zooms in further on the center)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    zoom: f64,
+
+    /// Default crop mode: fill (crop to fill the frame) or pad (letterbox the whole source)
+    #[arg(long, value_name = "MODE", default_value = "fill")]
+    fit: String,
+
+    /// Share of the height used by the top section in split modes (0.75 = 960 of 1280)
+    #[arg(long, value_name = "RATIO", default_value_t = yt_clipper_rust::crop::DEFAULT_SPLIT_RATIO)]
+    split_ratio: f64,
+
+    /// Draw a text watermark on every clip (e.g. "PREVIEW")
+    #[arg(long, value_name = "TEXT", conflicts_with = "logo")]
+    watermark: Option<String>,
+
+    /// Draw an image (e.g. a channel logo) on every clip
+    #[arg(long, value_name = "PATH")]
+    logo: Option<std::path::PathBuf>,
+
+    /// Overlay position: top-left, top-right, bottom-left, bottom-right, center
+    #[arg(long, default_value = "top-right")]
+    overlay_position: String,
+
+    /// Watermark opacity (0.0-1.0)
+    #[arg(long, value_name = "OPACITY", default_value_t = overlay::DEFAULT_TEXT_OPACITY)]
+    watermark_opacity: f64,
+
+    /// Logo width as a fraction of the frame width
+    #[arg(long, value_name = "FRACTION", default_value_t = overlay::DEFAULT_IMAGE_SCALE)]
+    logo_scale: f64,
+
+    /// Title drawn in a black band above the video; {title} is replaced with the video title
+    #[arg(long, value_name = "TEXT")]
+    header_text: Option<String>,
+
+    /// Output size as WIDTHxHEIGHT (defaults to 720x1280, or 1080x1080 for square)
+    #[arg(long)]
+    size: Option<String>,
+
     /// Enable auto subtitle using Faster-Whisper
     #[arg(short, long)]
     subtitle: bool,
 
-    /// Whisper model size: tiny, base, small, medium, large
+    /// Translate the speech into English subtitles instead of transcribing it
+    #[arg(long)]
+    translate: bool,
+
+    /// Two-line subtitles: the transcript with its English translation below (implies --subtitle)
+    #[arg(long)]
+    dual_subtitle: bool,
+
+    /// Whisper model: tiny, base, small, medium, large, turbo (large-v3-turbo),
+    /// distil-small.en or distil-large-v3 (distil models are English-only)
     #[arg(long, default_value = "small")]
     model: String,
 
-    /// Subtitle language code (e.g., id, en, ja)
+    /// Subtitle style: tiktok, minimal, boxed, karaoke
+    #[arg(long, default_value = "tiktok")]
+    subtitle_style: String,
+
+    /// Subtitle position: top, center, bottom [default: center for split modes, bottom otherwise]
+    #[arg(long)]
+    subtitle_position: Option<String>,
+
+    /// Active-word animation for word-highlight subtitles: pop, fade, slide, none
+    #[arg(long, default_value = "pop")]
+    highlight_animation: String,
+
+    /// Append an emoji after a word in subtitles, as WORD=EMOJI (repeatable, e.g. fire=🔥).
+    /// Needs an emoji font such as Noto Color Emoji installed for ffmpeg to draw it
+    #[arg(long, value_name = "WORD=EMOJI")]
+    emoji: Vec<String>,
+
+    /// Subtitle font for one language, as LANG=FONT (repeatable, e.g. ja="Noto Sans CJK JP").
+    /// Applies to the configured language, or the detected one with --language auto
+    #[arg(long, value_name = "LANG=FONT")]
+    language_font: Vec<String>,
+
+    /// Subtitle mode: burn (into the video) or embed (toggleable caption track, no re-encode)
+    #[arg(long, default_value = "burn")]
+    subtitle_mode: String,
+
+    /// Generate subtitles but don't burn or embed them; the .ass/.srt file is saved next to
+    /// each clip for editing (implies --subtitle)
+    #[arg(long)]
+    subtitle_only: bool,
+
+    /// Switch to a larger whisper model when the chosen one is known to transcribe the
+    /// language poorly (e.g. tiny for non-English), instead of only warning
+    #[arg(long)]
+    auto_upgrade_model: bool,
+
+    /// Only subtitle clips whose heatmap score is at least this (0.0-1.0)
+    #[arg(long, value_name = "SCORE")]
+    subtitle_min_score: Option<f64>,
+
+    /// Also save a subtitle file next to each clip: ass, or vtt (word-timed WebVTT)
+    #[arg(long, value_name = "FORMAT")]
+    subtitle_sidecar: Option<String>,
+
+    /// Whisper beam search width
+    #[arg(long, value_name = "N", default_value_t = subtitle::DEFAULT_BEAM_SIZE)]
+    beam_size: u32,
+
+    /// Whisper sampling temperature (0.0 = deterministic)
+    #[arg(long, value_name = "T", default_value_t = subtitle::DEFAULT_TEMPERATURE)]
+    temperature: f32,
+
+    /// Skip silence with voice activity detection (reduces hallucinated subtitles)
+    #[arg(long)]
+    vad: bool,
+
+    /// CPU threads for whisper.cpp transcription (default: all cores)
+    #[arg(long, value_name = "N")]
+    whisper_threads: Option<usize>,
+
+    /// Transcribe again even if a cached transcript exists for a clip
+    #[arg(long, conflicts_with = "restyle")]
+    retranscribe: bool,
+
+    /// Only re-style subtitles from cached transcripts; clips without one are not transcribed
+    #[arg(long)]
+    restyle: bool,
+
+    /// Subtitle an existing local video instead of clipping (writes <name>_subtitled.mp4)
+    #[arg(long, value_name = "PATH")]
+    subtitle_file: Option<std::path::PathBuf>,
+
+    /// Subtitle language code (e.g., id, en, ja), or "auto" to detect it
     #[arg(long, default_value = "id")]
     language: String,
 
-    /// Output directory for clips
+    /// Output directory for clips, or `-` to stream a single clip (--start/--end) to stdout
     #[arg(short, long, default_value = "clips")]
     output: String,
 
+    /// Print the time each clip spent in each stage (download, crop, transcribe, burn)
+    #[arg(long)]
+    profile: bool,
+
+    /// Cut one clip starting here (seconds) instead of using the heatmap
+    #[arg(long, value_name = "SECONDS", requires = "end")]
+    start: Option<f64>,
+
+    /// End (seconds) of the clip cut with --start
+    #[arg(long, value_name = "SECONDS", requires = "start")]
+    end: Option<f64>,
+
     /// Update yt-dlp before processing
     #[arg(long)]
     update: bool,
 
+    /// Fail instead of warning when ffmpeg or yt-dlp is older than the supported minimum
+    #[arg(long)]
+    strict_versions: bool,
+
     /// Run in interactive mode (prompts for all options)
     #[arg(short, long)]
     interactive: bool,
@@ -55,17 +216,277 @@ struct Args {
     /// Use GPU acceleration (NVIDIA NVENC) for video encoding
     #[arg(short, long)]
     gpu: bool,
+
+    /// Maximum number of clips to generate (0 = no limit, merges overlapping segments)
+    #[arg(long, default_value_t = yt_clipper_rust::MAX_CLIPS)]
+    max_clips: usize,
+
+    /// Merge overlapping segments into a single clip
+    #[arg(long)]
+    merge: bool,
+
+    /// Also join all clips into a single highlight reel (reel.mp4)
+    #[arg(long)]
+    reel: bool,
+
+    /// Add a chapter per clip to the reel, named by rank and source timestamp
+    #[arg(long, requires = "reel")]
+    reel_chapters: bool,
+
+    /// Produce silent clips (drops audio, disables subtitles)
+    #[arg(long)]
+    mute: bool,
+
+    /// Also save each clip uncropped (16:9) as clip_N_wide.mp4
+    #[arg(long)]
+    keep_horizontal: bool,
+
+    /// Also save a small looping preview of each clip's start: gif or webp
+    #[arg(long)]
+    preview: Option<String>,
+
+    /// Upscale sources below 720p with a sharper scaler before cropping (better quality, slower encode)
+    #[arg(long)]
+    upscale: bool,
+
+    /// Don't move the MP4 index to the front of each file (web-optimized by default)
+    #[arg(long)]
+    no_faststart: bool,
+
+    /// Export only the audio of each clip (skips cropping and subtitles)
+    #[arg(long)]
+    audio_only: bool,
+
+    /// Audio-only clip format: m4a or mp3
+    #[arg(long, default_value = "m4a")]
+    audio_format: String,
+
+    /// Audio codec of video clips: aac, opus or mp3
+    #[arg(long, default_value = "aac")]
+    audio_codec: String,
+
+    /// Audio bitrate of video clips (e.g. 128k, 192k)
+    #[arg(long, default_value = yt_clipper_rust::DEFAULT_AUDIO_BITRATE)]
+    audio_bitrate: String,
+
+    /// Audio channels of video clips (default: stereo)
+    #[arg(long, value_name = "N")]
+    audio_channels: Option<u32>,
+
+    /// Give up on a clip if downloading, cropping and subtitling take longer than this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    clip_timeout: Option<u64>,
+
+    /// Seconds of context added before and after each segment (0 = exact segment)
+    #[arg(long, value_name = "SECONDS", default_value_t = yt_clipper_rust::PADDING)]
+    padding: f64,
+
+    /// Fade clips in and out over this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    fade: Option<f64>,
+
+    /// Convert every clip to this frame rate (e.g. 30), for consistent compilations
+    #[arg(long, value_name = "FPS")]
+    fps: Option<f64>,
+
+    /// How often yt-dlp retries a failed download or fragment
+    #[arg(long, value_name = "N", default_value_t = yt_clipper_rust::DEFAULT_YTDLP_RETRIES)]
+    ytdlp_retries: u32,
+
+    /// Fragments yt-dlp downloads in parallel
+    #[arg(long, value_name = "N", default_value_t = yt_clipper_rust::DEFAULT_YTDLP_FRAGMENTS)]
+    ytdlp_fragments: u32,
+
+    /// Save a PNG chart of the heatmap with the generated clips marked; for a playlist, a
+    /// file of that name in each video's subdirectory (needs a build with --features heatmap-png)
+    #[arg(long, value_name = "FILE")]
+    heatmap_png: Option<std::path::PathBuf>,
+
+    /// Clip at most this many videos of a playlist link, each into its own subdirectory
+    /// (0 = the whole playlist)
+    #[arg(long, value_name = "N", default_value_t = yt_clipper_rust::DEFAULT_PLAYLIST_LIMIT)]
+    playlist_limit: usize,
+
+    /// Tighten each clip to its first and last spoken word, cutting the dead air the
+    /// padding adds (transcribes each clip, so whisper must be installed)
+    #[arg(long)]
+    trim_silence: bool,
+
+    /// Start every download from scratch instead of resuming partial files left by an
+    /// earlier failed attempt
+    #[arg(long)]
+    no_resume: bool,
+
+    /// Maximum total length of all clips in seconds; the best clips are kept until the
+    /// next one would exceed it
+    #[arg(long, value_name = "SECONDS")]
+    duration_budget: Option<f64>,
+
+    /// Proxy for scraping and yt-dlp (http://host:port or socks5://host:port).
+    /// Defaults to the HTTPS_PROXY environment variable
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// User-Agent for scraping YouTube and for yt-dlp (defaults to a generic browser UA)
+    #[arg(long, value_name = "UA")]
+    user_agent: Option<String>,
+
+    /// Accept-Language for scraping and yt-dlp, e.g. en-US, for stable page parsing
+    #[arg(long, value_name = "LANG")]
+    accept_language: Option<String>,
+
+    /// ffmpeg binary to use instead of the one on PATH. Defaults to the FFMPEG_BIN environment variable
+    #[arg(long, value_name = "PATH")]
+    ffmpeg_path: Option<std::path::PathBuf>,
+
+    /// yt-dlp binary to use instead of the one on PATH. Defaults to the YTDLP_BIN environment variable
+    #[arg(long, value_name = "PATH")]
+    ytdlp_path: Option<std::path::PathBuf>,
+
+    /// Extra ffmpeg flags for each clip's encode, inserted before the output file
+    /// (e.g. "-tune film"), split with shell quoting. Not validated: a clashing flag can break encoding
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+    ffmpeg_args: Option<String>,
+
+    /// Extra yt-dlp flags for every yt-dlp call (e.g. "--cookies-from-browser firefox"),
+    /// split with shell quoting
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+    ytdlp_args: Option<String>,
+
+    /// Where clips come from when no keywords are given: heatmap (most replayed), or
+    /// audio-energy (loudest moments, for music and reaction videos)
+    #[arg(long, value_name = "SOURCE", default_value = "heatmap")]
+    source: String,
+
+    /// Clip selection strategy: top-score, or diverse-windows to spread clips across the video
+    #[arg(long, default_value = "top-score")]
+    strategy: String,
+
+    /// How clip files are numbered: by-score (clip_1 is the hottest) or chronological
+    #[arg(long, value_name = "ORDER", default_value = "by-score")]
+    order: String,
+
+    /// Score weighting by position: flat, skip-intro (penalize the first 30s), or
+    /// late-climax (favor later segments)
+    #[arg(long, default_value = "flat")]
+    weighting: String,
+
+    /// Clip wherever these comma-separated words or phrases are spoken instead of at
+    /// heatmap peaks (transcribes the full video with the --model/--language settings)
+    #[arg(long, value_name = "WORDS")]
+    keywords: Option<String>,
+
+    /// Seconds around a chosen clip in which diverse-windows skips other segments
+    #[arg(long, value_name = "SECONDS", default_value_t = 60.0)]
+    diversity_window: f64,
+
+    /// Skip clips starting within this many seconds of an already chosen clip
+    #[arg(long, value_name = "SECONDS", default_value_t = 0.0)]
+    min_gap: f64,
+
+    /// Ignore heatmap segments starting in the first SECONDS of the video (intros, sponsors)
+    #[arg(long, value_name = "SECONDS", default_value_t = 0.0)]
+    skip_intro: f64,
+
+    /// Warn when two clips share more than this fraction of the shorter one (0.0-1.0)
+    #[arg(long, value_name = "FRACTION", default_value_t = selection::DEFAULT_MAX_OVERLAP)]
+    max_overlap: f64,
+
+    /// Drop the lower-scoring of two overlapping clips before encoding instead of warning
+    #[arg(long)]
+    reject_overlaps: bool,
+
+    /// Directory for intermediate files (defaults to the system temp directory)
+    #[arg(long, value_name = "DIR")]
+    temp_dir: Option<String>,
+
+    /// Only clip these 1-based segment ranks, e.g. 1,3,7
+    #[arg(long, value_name = "RANKS", conflicts_with = "rank_range")]
+    pick: Option<String>,
+
+    /// Only clip an inclusive range of segment ranks, e.g. 1-5
+    #[arg(long, value_name = "FIRST-LAST")]
+    rank_range: Option<String>,
+
+    /// Download the full video once and cut clips locally (saves bandwidth, uses more disk)
+    #[arg(long)]
+    download_full: bool,
+
+    /// Embed the source title, URL and clip time range into each clip's metadata
+    #[arg(long)]
+    embed_metadata: bool,
+
+    /// Write manifest.json describing the run and every clip to the output directory
+    #[arg(long)]
+    manifest: bool,
+
+    /// Open the output directory in the file manager when done
+    #[arg(long)]
+    open: bool,
+
+    /// Download a whisper.cpp model (tiny, base, small, medium, large, turbo, ...) and exit
+    #[arg(long, value_name = "SIZE")]
+    download_model: Option<String>,
+
+    /// Download all whisper.cpp models and exit
+    #[arg(long)]
+    download_all_models: bool,
+
+    /// Print version, dependency and backend diagnostics as JSON and exit
+    #[arg(long, visible_alias = "doctor")]
+    info: bool,
+
+    /// List the crop modes with their descriptions and exit
+    #[arg(long)]
+    list_crop_modes: bool,
+
+    /// Show more detail: -v adds debug output, -vv also prints every ffmpeg/yt-dlp command
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Print only the final summary and errors
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+/// Pass-through arguments (`--ffmpeg-args`, `--ytdlp-args`), split with shell quoting
+/// so values like `-metadata "title=My clip"` stay one argument. Exits on unbalanced quotes
+fn split_args(flag: &str, args: Option<&str>) -> Vec<String> {
+    let Some(args) = args else { return Vec::new() };
+    match shlex::split(args) {
+        Some(split) => split,
+        None => {
+            eprintln!("Invalid {} '{}'. Expected shell-style arguments with balanced quotes.", flag, args);
+            std::process::exit(RunOutcome::Failed.exit_code());
+        }
+    }
+}
+
+/// Log level for the `-v`/`-q` flags
+fn log_level(verbose: u8, quiet: bool) -> tracing::Level {
+    match (quiet, verbose) {
+        (true, _) => tracing::Level::ERROR,
+        (false, 0) => tracing::Level::INFO,
+        (false, 1) => tracing::Level::DEBUG,
+        (false, _) => tracing::Level::TRACE,
+    }
+}
+
+/// Log filter from RUST_LOG, or `default` when it is unset or invalid
+fn env_filter(default: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default))
 }
 
 fn prompt_crop_mode() -> CropMode {
-    println!("\n=== Crop Mode ===");
-    println!("1. Default (center crop)");
-    println!("2. Split Left (top: center, bottom: bottom-left facecam)");
-    println!("3. Split Right (top: center, bottom: bottom-right facecam)");
+    eprintln!("\n=== Crop Mode ===");
+    let modes = CropMode::all();
+    for (number, mode) in modes.iter().enumerate() {
+        eprintln!("{}. {}", number + 1, mode.description());
+    }
 
     loop {
-        print!("\nSelect crop mode (1-3): ");
-        io::stdout().flush().unwrap();
+        eprint!("\nSelect crop mode (1-{}): ", modes.len());
+        io::stderr().flush().unwrap();
 
         let mut input = String::new();
         if io::stdin().read_line(&mut input).is_err() {
@@ -73,24 +494,25 @@ fn prompt_crop_mode() -> CropMode {
         }
 
         if let Some(mode) = CropMode::from_input(input.trim()) {
-            println!("Selected: {}", mode.description());
+            eprintln!("Selected: {}", mode.description());
             return mode;
         }
-        println!("Invalid choice. Please enter 1, 2, or 3.");
+        eprintln!("Invalid choice. Please enter a number from 1 to {}.", modes.len());
     }
 }
 
 fn prompt_subtitle() -> (bool, WhisperModel) {
-    println!("\n=== Auto Subtitle ===");
-    println!("Available models:");
-    println!("  - tiny   ({})", WhisperModel::Tiny.size_display());
-    println!("  - base   ({})", WhisperModel::Base.size_display());
-    println!("  - small  ({}) [recommended]", WhisperModel::Small.size_display());
-    println!("  - medium ({})", WhisperModel::Medium.size_display());
-    println!("  - large  ({})", WhisperModel::Large.size_display());
+    eprintln!("\n=== Auto Subtitle ===");
+    eprintln!("Available models:");
+    eprintln!("  - tiny   ({})", WhisperModel::Tiny.size_display());
+    eprintln!("  - base   ({})", WhisperModel::Base.size_display());
+    eprintln!("  - small  ({}) [recommended]", WhisperModel::Small.size_display());
+    eprintln!("  - medium ({})", WhisperModel::Medium.size_display());
+    eprintln!("  - large  ({})", WhisperModel::Large.size_display());
+    eprintln!("  - turbo  ({}) [large quality, much faster]", WhisperModel::LargeV3Turbo.size_display());
 
-    print!("\nEnable subtitle? (y/n or model name): ");
-    io::stdout().flush().unwrap();
+    eprint!("\nEnable subtitle? (y/n or model name): ");
+    io::stderr().flush().unwrap();
 
     let mut input = String::new();
     if io::stdin().read_line(&mut input).is_err() {
@@ -101,7 +523,7 @@ fn prompt_subtitle() -> (bool, WhisperModel) {
 
     // Check if user typed a model name directly
     if let Some(model) = WhisperModel::from_input(&input_trimmed) {
-        println!("Subtitle enabled with model: {} ({})", model, model.size_display());
+        eprintln!("Subtitle enabled with model: {} ({})", model, model.size_display());
         return (true, model);
     }
 
@@ -109,57 +531,306 @@ fn prompt_subtitle() -> (bool, WhisperModel) {
     let enabled = matches!(input_trimmed.as_str(), "y" | "yes");
 
     if !enabled {
-        println!("Subtitle disabled.");
+        eprintln!("Subtitle disabled.");
         return (false, WhisperModel::Small);
     }
 
-    print!("Select model (tiny/base/small/medium/large) [small]: ");
-    io::stdout().flush().unwrap();
+    eprint!("Select model (tiny/base/small/medium/large/turbo) [small]: ");
+    io::stderr().flush().unwrap();
 
     let mut model_input = String::new();
     if io::stdin().read_line(&mut model_input).is_err() || model_input.trim().is_empty() {
-        println!("Using default model: small");
+        eprintln!("Using default model: small");
         return (true, WhisperModel::Small);
     }
 
     let model = WhisperModel::from_input(model_input.trim()).unwrap_or(WhisperModel::Small);
-    println!("Subtitle enabled with model: {} ({})", model, model.size_display());
+    eprintln!("Subtitle enabled with model: {} ({})", model, model.size_display());
     (true, model)
 }
 
 fn prompt_url() -> String {
-    print!("\nEnter YouTube URL: ");
-    io::stdout().flush().unwrap();
+    eprint!("\nEnter YouTube URL: ");
+    io::stderr().flush().unwrap();
 
     let mut link = String::new();
     io::stdin().read_line(&mut link).unwrap();
     link.trim().to_string()
 }
 
+/// Print where a run's clips, reel and warnings ended up
+fn print_result(result: &ProcessResult, output_dir: &str) {
+    println!(
+        "\nFinished processing. {} clip(s) successfully saved to '{}'.",
+        result.files.len(),
+        output_dir
+    );
+    if let Some(reel) = &result.reel {
+        println!("Highlight reel: {}", reel);
+    }
+    let previews = result.clips.iter().filter(|clip| clip.preview_file.is_some()).count();
+    if previews > 0 {
+        println!("{} preview(s) saved next to the clips.", previews);
+    }
+    if !result.warnings.is_empty() {
+        println!("{} warning(s):", result.warnings.len());
+        for warning in &result.warnings {
+            println!("  {}", warning);
+        }
+    }
+}
+
+/// Reveal a directory in the OS file manager. Does nothing in headless environments.
+fn open_in_file_manager(path: &str) {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        // No display server means nowhere to show a window
+        if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+            return;
+        }
+        "xdg-open"
+    };
+
+    if which::which(opener).is_err() {
+        return;
+    }
+
+    let _ = std::process::Command::new(opener).arg(path).spawn();
+}
+
+/// Diagnostics for bug reports: versions of this tool and its dependencies
+fn print_info(args: &Args) -> anyhow::Result<()> {
+    let info = serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "dependencies": deps::detect_versions(args.ffmpeg_path.as_deref(), args.ytdlp_path.as_deref()),
+        "subtitle_backends": subtitle::subtitle_backend_status(),
+    });
+    println!("{}", serde_json::to_string_pretty(&info)?);
+    Ok(())
+}
+
+fn download_models(models: &[WhisperModel]) -> anyhow::Result<()> {
+    for &model in models {
+        println!("\n=== Downloading {} model ({}) ===", model, model.size_display());
+        let path = download_whisper_model(model)?;
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        println!(
+            "  Saved to: {} ({:.1} MB)",
+            path.display(),
+            size as f64 / (1024.0 * 1024.0)
+        );
+    }
+    Ok(())
+}
+
+/// Subtitle settings shared by the clipping pipeline and `--subtitle-file`
+fn subtitle_config_from_args(
+    args: &Args,
+    enabled: bool,
+    model: WhisperModel,
+    language: &str,
+) -> anyhow::Result<SubtitleConfig> {
+    let subtitle_preset = SubtitlePreset::from_input(&args.subtitle_style).ok_or_else(|| {
+        anyhow!(
+            "Invalid subtitle style '{}'. Expected tiktok, minimal, boxed or karaoke.",
+            args.subtitle_style
+        )
+    })?;
+    let mode = SubtitleMode::from_input(&args.subtitle_mode)
+        .ok_or_else(|| anyhow!("Invalid subtitle mode '{}'. Expected burn or embed.", args.subtitle_mode))?;
+    let sidecar = match args.subtitle_sidecar.as_deref() {
+        Some(format) => Some(
+            SubtitleFormat::from_input(format)
+                .ok_or_else(|| anyhow!("Invalid subtitle sidecar '{}'. Expected ass or vtt.", format))?,
+        ),
+        None => None,
+    };
+    let position = match args.subtitle_position.as_deref() {
+        Some(position) => Some(
+            SubtitlePosition::from_input(position)
+                .ok_or_else(|| anyhow!("Invalid subtitle position '{}'. Expected top, center or bottom.", position))?,
+        ),
+        None => None,
+    };
+    let highlight_animation = HighlightAnimation::from_input(&args.highlight_animation).ok_or_else(|| {
+        anyhow!(
+            "Invalid highlight animation '{}'. Expected pop, fade, slide or none.",
+            args.highlight_animation
+        )
+    })?;
+
+    let mut subtitle_config = SubtitleConfig::new(enabled, model, language)
+        .with_preset(subtitle_preset)
+        .with_mode(mode)
+        .with_min_score(args.subtitle_min_score)
+        .with_sidecar(sidecar)
+        .with_decoding(args.beam_size, args.temperature, args.vad)
+        .with_whisper_threads(args.whisper_threads)
+        .with_task(if args.translate {
+            WhisperTask::Translate
+        } else {
+            WhisperTask::Transcribe
+        })
+        .with_dual(args.dual_subtitle)
+        .with_subtitle_only(args.subtitle_only)
+        .with_auto_upgrade_model(args.auto_upgrade_model)
+        .with_position(position)
+        .with_transcript_cache(if args.retranscribe {
+            TranscriptCache::Refresh
+        } else if args.restyle {
+            TranscriptCache::Only
+        } else {
+            TranscriptCache::Reuse
+        });
+    subtitle_config.ffmpeg_path = args.ffmpeg_path.clone();
+    subtitle_config.style.highlight_animation = highlight_animation;
+    for entry in &args.emoji {
+        match entry.split_once('=') {
+            Some((word, emoji)) if !word.trim().is_empty() && !emoji.trim().is_empty() => {
+                subtitle_config
+                    .style
+                    .emoji_map
+                    .insert(word.trim().to_string(), emoji.trim().to_string());
+            }
+            _ => return Err(anyhow!("Invalid --emoji '{}'. Expected WORD=EMOJI.", entry)),
+        }
+    }
+    for entry in &args.language_font {
+        match entry.split_once('=') {
+            Some((language, font)) if !language.trim().is_empty() && !font.trim().is_empty() => {
+                let mut style = subtitle_config.style.clone();
+                style.font_name = font.trim().to_string();
+                subtitle_config = subtitle_config.with_language_style(language, style);
+            }
+            _ => return Err(anyhow!("Invalid --language-font '{}'. Expected LANG=FONT.", entry)),
+        }
+    }
+    Ok(subtitle_config)
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    // Check dependencies (ffmpeg, yt-dlp)
-    if let Err(e) = check_dependencies() {
+    // RUST_LOG overrides the defaults: info for the server, -v/-q for the CLI, which also
+    // logs to stderr so a clip streamed to stdout isn't corrupted
+    if args.server {
+        tracing_subscriber::fmt().with_env_filter(env_filter("info")).init();
+    } else {
+        let default = format!("yt_clipper_rust={}", log_level(args.verbose, args.quiet));
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter(&default))
+            .with_writer(io::stderr)
+            .with_target(false)
+            .without_time()
+            .init();
+    }
+
+    if args.info {
+        return print_info(&args);
+    }
+    if args.list_crop_modes {
+        for mode in CropMode::all() {
+            let info = mode.info();
+            let gpu = if info.gpu_recommended { "  [--gpu recommended]" } else { "" };
+            println!("{:<16} {}{}", info.id, info.description, gpu);
+        }
+        return Ok(());
+    }
+    // Model download mode (no ffmpeg/yt-dlp needed)
+    if args.download_all_models {
+        return download_models(WhisperModel::all());
+    }
+    if let Some(name) = args.download_model.as_deref() {
+        let Some(model) = WhisperModel::from_input(name) else {
+            let models: Vec<String> = WhisperModel::all().iter().map(ToString::to_string).collect();
+            eprintln!("Unknown model '{}'. Use one of: {}.", name, models.join(", "));
+            std::process::exit(RunOutcome::Failed.exit_code());
+        };
+        return download_models(&[model]);
+    }
+
+    // Subtitle an existing file, without the YouTube pipeline
+    // (only ffmpeg and a whisper backend are needed)
+    if let Some(video) = args.subtitle_file.as_deref() {
+        if let Err(e) = deps::ensure_ffmpeg(args.ffmpeg_path.as_deref()).and_then(|_| deps::ensure_whisper()) {
+            eprintln!("Error checking dependencies: {}", e);
+            std::process::exit(RunOutcome::Failed.exit_code());
+        }
+        let Some(language) = parse_language(&args.language) else {
+            eprintln!("Invalid language '{}'. Expected a code like en or id, or auto.", args.language);
+            std::process::exit(RunOutcome::Failed.exit_code());
+        };
+        let model = WhisperModel::from_input(&args.model).unwrap_or(WhisperModel::Small);
+        let config = match subtitle_config_from_args(&args, true, model, &language) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+        };
+        let temp_dir = args
+            .temp_dir
+            .clone()
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(yt_clipper_rust::default_temp_dir);
+
+        tracing::info!("Subtitling {} ({}, {})", video.display(), model, language);
+        let output = subtitle::subtitle_local_file(video, &config, &temp_dir, args.gpu, &yt_clipper_rust::SystemRunner)?;
+        println!("Saved: {}", output.display());
+        return Ok(());
+    }
+
+    // Check dependencies (ffmpeg, yt-dlp, and whisper for keyword search)
+    let keywords = args
+        .keywords
+        .as_deref()
+        .map(selection::parse_keywords)
+        .unwrap_or_default();
+    if let Err(e) = check_dependencies(
+        args.strict_versions,
+        args.ffmpeg_path.as_deref(),
+        args.ytdlp_path.as_deref(),
+    )
+    .and_then(|_| {
+        if keywords.is_empty() && !args.trim_silence {
+            Ok(())
+        } else {
+            deps::ensure_whisper()
+        }
+    }) {
         eprintln!("Error checking dependencies: {}", e);
-        std::process::exit(1);
+        std::process::exit(RunOutcome::Failed.exit_code());
     }
 
     // Update yt-dlp if requested
     if args.update {
-        let _ = update_ytdlp();
+        let _ = update_ytdlp(args.ytdlp_path.as_deref());
     }
 
     // Server mode
     if args.server {
-        server::start_server(args.port).await;
+        let config = server::ServerConfig {
+            clips_root: args.output.clone().into(),
+            max_concurrent_jobs: args.max_jobs,
+            requests_per_minute: args.rate_limit,
+            api_token: std::env::var(server::API_TOKEN_ENV).ok(),
+            ffmpeg_path: args.ffmpeg_path.clone(),
+            ytdlp_path: args.ytdlp_path.clone(),
+            proxy: args.proxy.clone(),
+        };
+        server::start_server(args.port, config).await;
         return Ok(());
     }
 
     // Determine options - interactive or from args
     let (crop_mode, subtitle_enabled, whisper_model, language, url) = if args.interactive {
-        // Interactive mode
+        // Interactive mode; prompts go to stderr, so `--output -` keeps stdout for the clip
         let crop_mode = prompt_crop_mode();
         let (subtitle_enabled, whisper_model) = prompt_subtitle();
         let url = prompt_url();
@@ -169,55 +840,308 @@ async fn main() -> anyhow::Result<()> {
         let crop_mode = CropMode::from_input(&args.crop).unwrap_or(CropMode::Default);
         let whisper_model = WhisperModel::from_input(&args.model).unwrap_or(WhisperModel::Small);
 
-        let url = if let Some(u) = args.url {
+        let url = if let Some(u) = args.url.clone() {
             u
         } else {
             // Prompt for URL if not provided
             prompt_url()
         };
 
-        (crop_mode, args.subtitle, whisper_model, args.language.clone(), url)
+        let subtitle_enabled = args.subtitle || args.dual_subtitle || args.subtitle_only;
+        (crop_mode, subtitle_enabled, whisper_model, args.language.clone(), url)
     };
 
     if url.is_empty() {
-        println!("Invalid input. No URL provided.");
-        return Ok(());
+        eprintln!("Invalid input. No URL provided.");
+        std::process::exit(RunOutcome::Failed.exit_code());
     }
 
     // Build process options (SubtitleConfig::new auto-detects backend)
-    let subtitle_config = SubtitleConfig::new(
-        subtitle_enabled,
-        whisper_model,
-        &language,
-    );
+    let Some(language) = parse_language(&language) else {
+        eprintln!("Invalid language '{}'. Expected a code like en or id, or auto.", language);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+
+    let subtitle_config = match subtitle_config_from_args(&args, subtitle_enabled, whisper_model, &language) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(RunOutcome::Failed.exit_code());
+        }
+    };
+
+    let Some(audio_format) = AudioFormat::from_input(&args.audio_format) else {
+        eprintln!("Invalid audio format '{}'. Expected m4a or mp3.", args.audio_format);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+
+    let Some(audio_codec) = AudioCodec::from_input(&args.audio_codec) else {
+        eprintln!("Invalid audio codec '{}'. Expected aac, opus or mp3.", args.audio_codec);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+
+    let Some(fit_mode) = FitMode::from_input(&args.fit) else {
+        eprintln!("Invalid fit mode '{}'. Expected fill or pad.", args.fit);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
 
-    let options = ProcessOptions::new(crop_mode, subtitle_config, &args.output)
-        .with_gpu(args.gpu);
+    let preview = match args.preview.as_deref() {
+        Some(format) => match PreviewFormat::from_input(format) {
+            Some(preview) => Some(preview),
+            None => {
+                eprintln!("Invalid preview format '{}'. Expected gif or webp.", format);
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+        },
+        None => None,
+    };
 
-    println!("\n=== Processing ===");
-    println!("URL: {}", url);
-    println!("Crop mode: {}", crop_mode.description());
-    println!("Subtitle: {}", if options.subtitle.enabled {
-        format!("enabled ({}, {})", options.subtitle.model, options.subtitle.language)
+    let Some(segment_source) = SegmentSource::from_input(&args.source) else {
+        eprintln!("Invalid source '{}'. Expected heatmap or audio-energy.", args.source);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+
+    let Some(output_order) = OutputOrder::from_input(&args.order) else {
+        eprintln!("Invalid order '{}'. Expected by-score or chronological.", args.order);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+
+    let Some(strategy) = SelectionStrategy::from_input(&args.strategy) else {
+        eprintln!("Invalid strategy '{}'. Expected top-score or diverse-windows.", args.strategy);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+
+    let Some(weighting) = ScoreWeighting::from_input(&args.weighting) else {
+        eprintln!(
+            "Invalid weighting '{}'. Expected flat, skip-intro or late-climax.",
+            args.weighting
+        );
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+
+    let Some(overlay_position) = OverlayPosition::from_input(&args.overlay_position) else {
+        eprintln!(
+            "Invalid overlay position '{}'. Expected top-left, top-right, bottom-left, bottom-right or center.",
+            args.overlay_position
+        );
+        std::process::exit(RunOutcome::Failed.exit_code());
+    };
+    let overlay = match (&args.watermark, &args.logo) {
+        (Some(content), _) => Some(OverlaySpec::Text {
+            content: content.clone(),
+            position: overlay_position,
+            opacity: args.watermark_opacity,
+        }),
+        (None, Some(path)) => {
+            if !path.exists() {
+                eprintln!("Logo file not found: {}", path.display());
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+            Some(OverlaySpec::Image {
+                path: path.clone(),
+                position: overlay_position,
+                scale: args.logo_scale,
+            })
+        }
+        (None, None) => None,
+    };
+
+    let pick = match (args.pick.as_deref(), args.rank_range.as_deref()) {
+        (Some(list), _) => match selection::parse_rank_list(list) {
+            Some(ranks) => Some(ranks),
+            None => {
+                eprintln!(
+                    "Invalid --pick '{}'. Expected ranks like 1,3,7 (at most {}).",
+                    list,
+                    selection::MAX_RANK
+                );
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+        },
+        (None, Some(range)) => match selection::parse_rank_range(range) {
+            Some(ranks) => Some(ranks),
+            None => {
+                eprintln!(
+                    "Invalid --rank-range '{}'. Expected a range like 1-5 (at most {}).",
+                    range,
+                    selection::MAX_RANK
+                );
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+        },
+        (None, None) => None,
+    };
+
+    let output_spec = match args.size.as_deref() {
+        Some(size) => match OutputSpec::parse(size) {
+            Some(spec) => Some(spec),
+            None => {
+                eprintln!("Invalid size '{}'. Expected WIDTHxHEIGHT with even numbers.", size);
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+        },
+        None => None,
+    };
+
+    let ffmpeg_args = split_args("--ffmpeg-args", args.ffmpeg_args.as_deref());
+    let ytdlp_args = split_args("--ytdlp-args", args.ytdlp_args.as_deref());
+
+    let max_clips = if args.max_clips == 0 {
+        None
+    } else {
+        Some(args.max_clips)
+    };
+
+    let mut options = ProcessOptions::new(crop_mode, subtitle_config, &args.output)
+        .with_gpu(args.gpu)
+        .with_max_clips(max_clips)
+        .with_total_duration_budget(args.duration_budget)
+        .with_profile(args.profile)
+        .with_merge_segments(args.merge)
+        .with_output_spec(output_spec)
+        .with_concat_reel(args.reel)
+        .with_reel_chapters(args.reel_chapters)
+        .with_proxy(args.proxy.clone())
+        .with_user_agent(args.user_agent.clone())
+        .with_accept_language(args.accept_language.clone())
+        .with_ffmpeg_path(args.ffmpeg_path.clone())
+        .with_ytdlp_path(args.ytdlp_path.clone())
+        .with_extra_args(ffmpeg_args, ytdlp_args)
+        .with_mute(args.mute)
+        .with_keep_horizontal(args.keep_horizontal)
+        .with_preview(preview)
+        .with_upscale(args.upscale)
+        .with_trim_silence(args.trim_silence)
+        .with_faststart(!args.no_faststart)
+        .with_per_clip_timeout(args.clip_timeout.map(std::time::Duration::from_secs))
+        .with_ytdlp_retries(args.ytdlp_retries, args.ytdlp_fragments)
+        .with_resume_downloads(!args.no_resume)
+        .with_heatmap_png(args.heatmap_png.clone())
+        .with_padding(args.padding)
+        .with_fade(args.fade)
+        .with_target_fps(args.fps)
+        .with_download_full_first(args.download_full)
+        .with_selection_strategy(strategy, args.diversity_window)
+        .with_segment_source(segment_source)
+        .with_output_order(output_order)
+        .with_score_weighting(weighting)
+        .with_keywords(keywords)
+        .with_overlay(overlay)
+        .with_header_text(args.header_text.clone())
+        .with_min_gap_between_clips(args.min_gap)
+        .with_skip_intro_seconds(args.skip_intro)
+        .with_overlap_check(args.max_overlap, args.reject_overlaps)
+        .with_facecam_zoom(args.facecam_zoom)
+        .with_zoom(args.zoom)
+        .with_fit_mode(fit_mode)
+        .with_split_ratio(args.split_ratio)
+        .with_audio_only(args.audio_only, audio_format)
+        .with_audio_encoding(audio_codec, &args.audio_bitrate, args.audio_channels)
+        .with_pick(pick)
+        .with_embed_metadata(args.embed_metadata)
+        .with_write_manifest(args.manifest);
+    if let Some(temp_dir) = &args.temp_dir {
+        options = options.with_temp_dir(temp_dir);
+    }
+    if let Err(e) = options.check_audio_encoding() {
+        eprintln!("{}", e);
+        std::process::exit(RunOutcome::Failed.exit_code());
+    }
+
+    // Single clip at a given range; streaming keeps stdout free of everything but video
+    let streaming = args.output == yt_clipper_rust::STDOUT_OUTPUT;
+    if let (Some(start), Some(end)) = (args.start, args.end) {
+        match yt_clipper_rust::process_manual_clip(&url, start, end, &args.output, &options) {
+            Ok(path) if !streaming => println!("Clip saved: {}", path),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+        }
+        return Ok(());
+    } else if streaming {
+        eprintln!("--output - streams a single clip; give its range with --start and --end.");
+        std::process::exit(RunOutcome::Failed.exit_code());
+    }
+
+    tracing::info!("=== Processing ===");
+    tracing::info!("URL: {}", url);
+    tracing::info!("Crop mode: {}", crop_mode.description());
+    let spec = options.resolved_output_spec();
+    tracing::info!("Output size: {}x{}", spec.width, spec.height);
+    tracing::info!("Subtitle: {}", if options.subtitle.enabled {
+        format!(
+            "enabled ({}, {}, {})",
+            options.subtitle.model,
+            options.subtitle.language,
+            options.subtitle.preset.description()
+        )
     } else {
         "disabled".to_string()
     });
-    println!("GPU acceleration: {}", if args.gpu { "enabled (NVENC)" } else { "disabled" });
-    println!("Output: {}", args.output);
-    println!();
+    tracing::info!("GPU acceleration: {}", if args.gpu { "enabled (NVENC)" } else { "disabled" });
+    tracing::info!("Max clips: {}", match options.max_clips {
+        Some(max) => max.to_string(),
+        None => "no limit".to_string(),
+    });
+    tracing::info!("Output: {}", args.output);
+
+    // Playlists are clipped one video at a time, each into <output>/<video id>
+    if let Some(list_id) = extract_playlist_id(&url) {
+        let limit = (args.playlist_limit > 0).then_some(args.playlist_limit);
+        let ids = match expand_playlist(&list_id, limit, &options) {
+            Ok(ids) => ids,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(RunOutcome::Failed.exit_code());
+            }
+        };
+        tracing::info!("Playlist {}: clipping {} video(s).", list_id, ids.len());
+
+        let mut clipped = 0;
+        let mut outcomes = Vec::new();
+        for (i, video_id) in ids.iter().enumerate() {
+            tracing::info!("=== Video {}/{}: {} ===", i + 1, ids.len(), video_id);
+            let mut video_options = options.clone();
+            video_options.output_dir = std::path::Path::new(&args.output)
+                .join(video_id)
+                .to_string_lossy()
+                .to_string();
+            // One chart per video, named like the requested one, in the video's subdirectory
+            video_options.heatmap_png = options.heatmap_png.as_ref().map(|path| {
+                std::path::Path::new(&video_options.output_dir).join(path.file_name().unwrap_or(path.as_os_str()))
+            });
+            match full_process(&format!("https://youtu.be/{}", video_id), &video_options).await {
+                Ok(result) => {
+                    print_result(&result, &video_options.output_dir);
+                    outcomes.push(result.outcome(options.max_clips));
+                    clipped += 1;
+                }
+                Err(e) => {
+                    eprintln!("Error ({}): {}", video_id, e);
+                    outcomes.push(RunOutcome::Failed);
+                }
+            }
+        }
+        println!("\nFinished playlist: {} of {} video(s) clipped into '{}'.", clipped, ids.len(), args.output);
+        if args.open {
+            open_in_file_manager(&args.output);
+        }
+        std::process::exit(RunOutcome::combine(&outcomes).exit_code());
+    }
 
     match full_process(&url, &options).await {
-        Ok(files) => {
-            println!(
-                "\nFinished processing. {} clip(s) successfully saved to '{}'.",
-                files.len(),
-                args.output
-            );
+        Ok(result) => {
+            print_result(&result, &args.output);
+            if args.open {
+                open_in_file_manager(&args.output);
+            }
+            std::process::exit(result.outcome(options.max_clips).exit_code());
         }
         Err(e) => {
             eprintln!("Error: {}", e);
+            std::process::exit(RunOutcome::Failed.exit_code());
         }
     }
-
-    Ok(())
 }
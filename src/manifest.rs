@@ -0,0 +1,104 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{ClipResult, ProcessOptions, Warning};
+
+/// File name of the run manifest written to the output directory
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// Machine-readable description of a processing run
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    pub source_url: String,
+    pub video_id: String,
+    pub title: Option<String>,
+    pub crop_mode: String,
+    pub subtitle: ManifestSubtitle,
+    pub clips: Vec<ClipResult>,
+    pub reel: Option<String>,
+    /// Soft failures of the run (see `ProcessResult::warnings`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+}
+
+/// Subtitle settings used for every clip in the run
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestSubtitle {
+    pub enabled: bool,
+    pub model: Option<String>,
+    pub language: Option<String>,
+    pub style: Option<String>,
+}
+
+impl ManifestSubtitle {
+    pub fn from_options(options: &ProcessOptions) -> Self {
+        let subtitle = &options.subtitle;
+        // Muted and audio-only runs never burn subtitles, whatever the config says
+        let enabled = subtitle.enabled && !options.mute && !options.audio_only;
+        Self {
+            enabled,
+            model: enabled.then(|| subtitle.model.to_string()),
+            language: enabled.then(|| subtitle.language.clone()),
+            style: enabled.then(|| subtitle.preset.to_string()),
+        }
+    }
+}
+
+/// Write `manifest` as pretty-printed JSON into `output_dir`, returning its path.
+pub fn write_manifest(output_dir: &str, manifest: &Manifest) -> Result<PathBuf> {
+    let path = Path::new(output_dir).join(MANIFEST_FILENAME);
+    fs::write(&path, serde_json::to_string_pretty(manifest)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CropMode, SubtitleConfig, WhisperModel};
+
+    #[test]
+    fn test_write_manifest() {
+        let dir = std::env::temp_dir().join(format!("yt-clipper-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output_dir = dir.to_string_lossy().to_string();
+
+        let options = ProcessOptions::new(
+            CropMode::Square,
+            SubtitleConfig::new(true, WhisperModel::Base, "en"),
+            &output_dir,
+        );
+        let manifest = Manifest {
+            source_url: "https://youtu.be/abc123".to_string(),
+            video_id: "abc123".to_string(),
+            title: Some("Demo".to_string()),
+            crop_mode: options.crop_mode.to_string(),
+            subtitle: ManifestSubtitle::from_options(&options),
+            clips: vec![ClipResult {
+                file: "clip_1.mp4".to_string(),
+                start: 50.0,
+                end: 80.0,
+                score: 0.9,
+                rank: 1,
+                position: 1,
+                wide_file: None,
+                preview_file: None,
+            }],
+            reel: None,
+            warnings: Vec::new(),
+        };
+
+        let path = write_manifest(&output_dir, &manifest).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(json["video_id"], "abc123");
+        assert_eq!(json["crop_mode"], "square");
+        assert_eq!(json["subtitle"]["model"], "base");
+        assert_eq!(json["subtitle"]["language"], "en");
+        assert_eq!(json["clips"][0]["file"], "clip_1.mp4");
+        assert_eq!(json["clips"][0]["end"], 80.0);
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumString};
+
+use crate::crop::OutputSpec;
+use crate::escape::{escape_drawtext_text, escape_filter_path};
+
+/// Distance (pixels) between an overlay and the frame edge
+pub const OVERLAY_MARGIN: u32 = 24;
+
+/// Default opacity of text watermarks
+pub const DEFAULT_TEXT_OPACITY: f64 = 0.5;
+
+/// Default logo width as a fraction of the frame width
+pub const DEFAULT_IMAGE_SCALE: f64 = 0.2;
+
+/// Height of the header band on a 1280px-tall frame; scaled with the frame height
+pub const HEADER_HEIGHT: u32 = 150;
+
+/// Fonts tried for `drawtext` when fontconfig may be unavailable
+const FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Bold.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
+    "/Library/Fonts/Arial Bold.ttf",
+    "C:/Windows/Fonts/arialbd.ttf",
+];
+
+/// Where an overlay is placed on the frame
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlayPosition {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl OverlayPosition {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().replace('_', "-").as_str() {
+            "top-left" | "tl" => Some(OverlayPosition::TopLeft),
+            "top-right" | "tr" => Some(OverlayPosition::TopRight),
+            "bottom-left" | "bl" => Some(OverlayPosition::BottomLeft),
+            "bottom-right" | "br" => Some(OverlayPosition::BottomRight),
+            "center" | "centre" => Some(OverlayPosition::Center),
+            _ => None,
+        }
+    }
+
+    /// `x:y` expressions given the frame size, overlay size and margin variable names
+    fn coordinates(&self, frame: (&str, &str), item: (&str, &str)) -> String {
+        let (fw, fh) = frame;
+        let (w, h) = item;
+        let m = OVERLAY_MARGIN;
+        match self {
+            OverlayPosition::TopLeft => format!("x={m}:y={m}"),
+            OverlayPosition::TopRight => format!("x={fw}-{w}-{m}:y={m}"),
+            OverlayPosition::BottomLeft => format!("x={m}:y={fh}-{h}-{m}"),
+            OverlayPosition::BottomRight => format!("x={fw}-{w}-{m}:y={fh}-{h}-{m}"),
+            OverlayPosition::Center => format!("x=({fw}-{w})/2:y=({fh}-{h})/2"),
+        }
+    }
+}
+
+/// Watermark or branding drawn on top of every clip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OverlaySpec {
+    /// Text drawn with FFmpeg's `drawtext` (e.g. a "PREVIEW" watermark)
+    Text {
+        content: String,
+        position: OverlayPosition,
+        /// 0.0 (invisible) to 1.0 (opaque)
+        opacity: f64,
+    },
+    /// Image composited with FFmpeg's `overlay` (e.g. a channel logo)
+    Image {
+        path: PathBuf,
+        position: OverlayPosition,
+        /// Overlay width as a fraction of the frame width
+        scale: f64,
+    },
+}
+
+impl OverlaySpec {
+    /// Image file that must be passed to ffmpeg as an extra input, if any
+    pub fn image_input(&self) -> Option<&Path> {
+        match self {
+            OverlaySpec::Image { path, .. } => Some(path),
+            OverlaySpec::Text { .. } => None,
+        }
+    }
+
+    /// Filtergraph fragment reading `[input]` and writing `[output]`.
+    ///
+    /// For images, `image_index` is the ffmpeg input index of `image_input()`.
+    pub fn filter(&self, input: &str, output: &str, spec: &OutputSpec, image_index: usize) -> String {
+        match self {
+            OverlaySpec::Text {
+                content,
+                position,
+                opacity,
+            } => {
+                let font = match find_font() {
+                    Some(font) => format!("fontfile={}:", escape_filter_path(&font.to_string_lossy())),
+                    None => String::new(),
+                };
+                format!(
+                    "[{input}]drawtext={font}text={}:expansion=none:fontsize={}:fontcolor=white@{:.2}:\
+                     borderw=2:bordercolor=black@{:.2}:{}[{output}]",
+                    escape_drawtext_text(content),
+                    (spec.height / 24).max(12),
+                    opacity.clamp(0.0, 1.0),
+                    opacity.clamp(0.0, 1.0),
+                    position.coordinates(("w", "h"), ("tw", "th")),
+                )
+            }
+            OverlaySpec::Image {
+                position, scale, ..
+            } => {
+                let width = ((spec.width as f64 * scale.clamp(0.01, 1.0)) as u32).max(2) & !1;
+                format!(
+                    "[{image_index}:v]scale={width}:-2,format=rgba[logo];\
+                     [{input}][logo]overlay={}:eof_action=repeat[{output}]",
+                    position.coordinates(("W", "H"), ("w", "h")),
+                )
+            }
+        }
+    }
+}
+
+/// Height of the header band for a `frame` (even, at least 2px)
+pub fn header_height(frame: &OutputSpec) -> u32 {
+    ((frame.height as u64 * HEADER_HEIGHT as u64 / 1280) as u32).max(2) & !1
+}
+
+/// Filters turning a video of `frame` minus the header band into `frame`: the video is
+/// pushed down below a black band with `text` centred in it.
+///
+/// The font shrinks for long titles so the text fits the frame width.
+pub fn header_filter(text: &str, frame: &OutputSpec) -> String {
+    let band = header_height(frame);
+    let chars = text.chars().count().max(1) as u32;
+    // Bold sans glyphs average a little over half the font size in width
+    let fitting = (frame.width.saturating_sub(2 * OVERLAY_MARGIN) * 9 / 5) / chars;
+    let fontsize = (band * 2 / 5).min(fitting).max(12);
+    let font = match find_font() {
+        Some(font) => format!("fontfile={}:", escape_filter_path(&font.to_string_lossy())),
+        None => String::new(),
+    };
+    format!(
+        "pad={}:{}:(ow-iw)/2:{band}:color=black,\
+         drawtext={font}text={}:expansion=none:fontsize={fontsize}:fontcolor=white:\
+         x=(w-tw)/2:y=({band}-th)/2",
+        frame.width,
+        frame.height,
+        escape_drawtext_text(text),
+    )
+}
+
+/// First bundled system font found for `drawtext`.
+///
+/// When none is found, `drawtext` falls back to fontconfig's default font, which only
+/// works if ffmpeg was built with fontconfig.
+pub fn find_font() -> Option<PathBuf> {
+    FONT_CANDIDATES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_overlay_filter() {
+        let overlay = OverlaySpec::Text {
+            content: "PREVIEW: draft".to_string(),
+            position: OverlayPosition::BottomRight,
+            opacity: 0.4,
+        };
+        let filter = overlay.filter("base", "out", &OutputSpec::default(), 1);
+
+        assert!(filter.starts_with("[base]drawtext="));
+        assert!(filter.ends_with("[out]"));
+        assert!(filter.contains(r"text=PREVIEW\\: draft:expansion=none"));
+        assert!(filter.contains("fontcolor=white@0.40"));
+        assert!(filter.contains("x=w-tw-24:y=h-th-24"));
+        assert_eq!(overlay.image_input(), None);
+    }
+
+    #[test]
+    fn test_image_overlay_filter() {
+        let overlay = OverlaySpec::Image {
+            path: PathBuf::from("logo.png"),
+            position: OverlayPosition::TopLeft,
+            scale: 0.25,
+        };
+        assert_eq!(
+            overlay.filter("base", "out", &OutputSpec::default(), 1),
+            "[1:v]scale=180:-2,format=rgba[logo];\
+             [base][logo]overlay=x=24:y=24:eof_action=repeat[out]"
+        );
+        assert_eq!(overlay.image_input(), Some(Path::new("logo.png")));
+        assert_eq!(OverlayPosition::from_input("top_right"), Some(OverlayPosition::TopRight));
+    }
+
+    #[test]
+    fn test_header_filter() {
+        let frame = OutputSpec::default();
+        assert_eq!(header_height(&frame), 150);
+        assert_eq!(header_height(&OutputSpec::new(1080, 1920)), 224);
+
+        let filter = header_filter("Best: moment", &frame);
+        assert!(filter.starts_with("pad=720:1280:(ow-iw)/2:150:color=black,drawtext="));
+        assert!(filter.contains(r"text=Best\\: moment:expansion=none:fontsize=60:"));
+        assert!(filter.ends_with("x=(w-tw)/2:y=(150-th)/2"));
+
+        // Long titles shrink to fit the width
+        let long = "A".repeat(80);
+        assert!(header_filter(&long, &frame).contains("fontsize=15:"));
+    }
+}
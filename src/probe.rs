@@ -0,0 +1,270 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::runner::CommandRunner;
+
+/// What ffprobe reports about a video file's first video stream and its container
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VideoInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Length in seconds, from the container (or the stream when the container has none)
+    pub duration: f64,
+    /// Average frame rate; `None` when ffprobe can't tell (e.g. a still image)
+    pub fps: Option<f64>,
+    /// Video codec name, e.g. `h264` or `vp9`
+    pub codec: String,
+    /// Container tags (`title`, `comment`, ...), keys lowercased
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pix_fmt: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    duration: Option<String>,
+}
+
+/// The parameters of one stream that must match for files to be joined by stream copy
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamParams {
+    /// `video`, `audio`, `subtitle`, ...
+    pub codec_type: String,
+    pub codec: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub pix_fmt: Option<String>,
+    pub sample_rate: Option<String>,
+    pub channels: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+/// Build the ffprobe command that prints every stream and the container of `path` as JSON
+pub fn build_probe_command(path: &Path, ffprobe: &Path) -> Command {
+    let mut cmd = Command::new(ffprobe);
+    cmd.args(["-v", "quiet", "-print_format", "json", "-show_streams", "-show_format"])
+        .arg(path);
+    cmd
+}
+
+/// Frame rate from ffprobe's `num/den` notation; `0/0` means unknown
+fn parse_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.split_once('/')?;
+    let (num, den): (f64, f64) = (num.trim().parse().ok()?, den.trim().parse().ok()?);
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+/// Parse the JSON printed by `build_probe_command`
+pub fn parse_probe_output(json: &str) -> Result<VideoInfo> {
+    let output: ProbeOutput =
+        serde_json::from_str(json).map_err(|e| anyhow!("Unexpected ffprobe output: {}", e))?;
+    let stream = output
+        .streams
+        .into_iter()
+        .find(|stream| stream.codec_type.as_deref() == Some("video"))
+        .ok_or_else(|| anyhow!("No video stream found"))?;
+    let (Some(width), Some(height)) = (stream.width, stream.height) else {
+        return Err(anyhow!("ffprobe reported no frame size"));
+    };
+
+    let format_duration = output.format.as_ref().and_then(|format| format.duration.as_deref());
+    let duration = format_duration
+        .or(stream.duration.as_deref())
+        .and_then(|duration| duration.trim().parse::<f64>().ok())
+        .ok_or_else(|| anyhow!("ffprobe reported no duration"))?;
+    let fps = stream
+        .avg_frame_rate
+        .as_deref()
+        .and_then(parse_rate)
+        .or_else(|| stream.r_frame_rate.as_deref().and_then(parse_rate));
+    let tags = output
+        .format
+        .map(|format| format.tags)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| (key.to_lowercase(), value))
+        .collect();
+
+    Ok(VideoInfo {
+        width,
+        height,
+        duration: duration.max(0.0),
+        fps,
+        codec: stream.codec_name.unwrap_or_default(),
+        tags,
+    })
+}
+
+/// Run `build_probe_command` on `path` and return its JSON
+fn run_probe(path: &Path, ffprobe: &Path, runner: &dyn CommandRunner) -> Result<String> {
+    let output = runner.output(&mut build_probe_command(path, ffprobe))?;
+    if !output.success {
+        return Err(anyhow!(
+            "ffprobe could not read {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Size, duration, frame rate, codec and tags of the video at `path`, via ffprobe
+pub fn probe_video(path: &Path, ffprobe: &Path, runner: &dyn CommandRunner) -> Result<VideoInfo> {
+    parse_probe_output(&run_probe(path, ffprobe, runner)?)
+}
+
+/// Parse the length in seconds from the JSON printed by `build_probe_command`, for any
+/// media file: the container's, or else the longest stream's
+pub fn parse_media_duration(json: &str) -> Result<f64> {
+    let output: ProbeOutput =
+        serde_json::from_str(json).map_err(|e| anyhow!("Unexpected ffprobe output: {}", e))?;
+    let parse = |duration: Option<&str>| duration.and_then(|duration| duration.trim().parse::<f64>().ok());
+    let stream_duration = output
+        .streams
+        .iter()
+        .filter_map(|stream| parse(stream.duration.as_deref()))
+        .reduce(f64::max);
+    parse(output.format.as_ref().and_then(|format| format.duration.as_deref()))
+        .or(stream_duration)
+        .map(|duration| duration.max(0.0))
+        .ok_or_else(|| anyhow!("ffprobe reported no duration"))
+}
+
+/// Length in seconds of the media file at `path` (video or audio-only), via ffprobe
+pub fn probe_media_duration(path: &Path, ffprobe: &Path, runner: &dyn CommandRunner) -> Result<f64> {
+    parse_media_duration(&run_probe(path, ffprobe, runner)?)
+}
+
+/// Parse the parameters of every stream, in order, from the JSON printed by
+/// `build_probe_command`
+pub fn parse_stream_params(json: &str) -> Result<Vec<StreamParams>> {
+    let output: ProbeOutput =
+        serde_json::from_str(json).map_err(|e| anyhow!("Unexpected ffprobe output: {}", e))?;
+    Ok(output
+        .streams
+        .into_iter()
+        .map(|stream| StreamParams {
+            codec_type: stream.codec_type.unwrap_or_default(),
+            codec: stream.codec_name.unwrap_or_default(),
+            width: stream.width,
+            height: stream.height,
+            pix_fmt: stream.pix_fmt,
+            sample_rate: stream.sample_rate,
+            channels: stream.channels,
+        })
+        .collect())
+}
+
+/// Codec parameters of every stream of the media file at `path`, via ffprobe
+pub fn probe_stream_params(path: &Path, ffprobe: &Path, runner: &dyn CommandRunner) -> Result<Vec<StreamParams>> {
+    parse_stream_params(&run_probe(path, ffprobe, runner)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed `ffprobe -show_streams -show_format` output of a 1080p YouTube download
+    const YOUTUBE_MP4: &str = r#"{
+    "streams": [
+        {
+            "index": 0,
+            "codec_name": "h264",
+            "codec_type": "video",
+            "width": 1920,
+            "height": 1080,
+            "r_frame_rate": "30000/1001",
+            "avg_frame_rate": "30000/1001",
+            "duration": "95.461367"
+        },
+        {
+            "index": 1,
+            "codec_name": "aac",
+            "codec_type": "audio",
+            "sample_rate": "44100",
+            "channels": 2,
+            "r_frame_rate": "0/0",
+            "avg_frame_rate": "0/0",
+            "duration": "95.504943"
+        }
+    ],
+    "format": {
+        "filename": "recording.mp4",
+        "nb_streams": 2,
+        "format_name": "mov,mp4,m4a,3gp,3g2,mj2",
+        "duration": "95.504943",
+        "tags": {
+            "major_brand": "isom",
+            "COMMENT": "Clipped from https://youtu.be/abc123 at 0:00-0:01"
+        }
+    }
+}"#;
+
+    #[test]
+    fn test_parse_probe_output() {
+        let info = parse_probe_output(YOUTUBE_MP4).unwrap();
+        assert_eq!((info.width, info.height), (1920, 1080));
+        assert_eq!(info.duration, 95.504943);
+        assert!((info.fps.unwrap() - 29.97).abs() < 0.01);
+        assert_eq!(info.codec, "h264");
+        assert_eq!(
+            info.tags.get("comment").map(String::as_str),
+            Some("Clipped from https://youtu.be/abc123 at 0:00-0:01")
+        );
+
+        // WebM downloads keep the duration on the stream, and still images have no rate
+        let webm = r#"{"streams": [{"codec_type": "video", "codec_name": "vp9", "width": 854,
+            "height": 480, "avg_frame_rate": "0/0", "duration": "12.5"}], "format": {}}"#;
+        let info = parse_probe_output(webm).unwrap();
+        assert_eq!(info.duration, 12.5);
+        assert_eq!(info.fps, None);
+
+        let audio = r#"{"streams": [{"codec_type": "audio", "codec_name": "opus"}], "format": {"duration": "3.0"}}"#;
+        assert!(parse_probe_output(audio).is_err());
+        assert!(parse_probe_output("").is_err());
+    }
+
+    #[test]
+    fn test_parse_media_duration() {
+        assert_eq!(parse_media_duration(YOUTUBE_MP4).unwrap(), 95.504943);
+        let audio = r#"{"streams": [{"codec_type": "audio", "codec_name": "opus"}], "format": {"duration": "3.0"}}"#;
+        assert_eq!(parse_media_duration(audio).unwrap(), 3.0);
+        let webm = r#"{"streams": [{"codec_type": "audio", "duration": "2.5"}, {"duration": "4.0"}], "format": {}}"#;
+        assert_eq!(parse_media_duration(webm).unwrap(), 4.0);
+        assert!(parse_media_duration(r#"{"streams": [], "format": {}}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_stream_params() {
+        let params = parse_stream_params(YOUTUBE_MP4).unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!((params[0].codec_type.as_str(), params[0].codec.as_str()), ("video", "h264"));
+        assert_eq!((params[0].width, params[0].height), (Some(1920), Some(1080)));
+        assert_eq!(params[1].sample_rate.as_deref(), Some("44100"));
+        assert_eq!(params[1].channels, Some(2));
+        assert!(parse_stream_params("").is_err());
+    }
+}
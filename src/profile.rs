@@ -0,0 +1,96 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+fn as_secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Wall-clock time one clip spent in each pipeline stage (serialized as seconds)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct StageTimings {
+    /// yt-dlp download of the clip's range
+    #[serde(serialize_with = "as_secs")]
+    #[schemars(with = "f64")]
+    pub download: Duration,
+    /// FFmpeg crop/encode (or audio extraction for audio-only clips)
+    #[serde(serialize_with = "as_secs")]
+    #[schemars(with = "f64")]
+    pub crop: Duration,
+    /// Whisper transcription, including audio extraction
+    #[serde(serialize_with = "as_secs")]
+    #[schemars(with = "f64")]
+    pub transcribe: Duration,
+    /// Burning or embedding the subtitles
+    #[serde(serialize_with = "as_secs")]
+    #[schemars(with = "f64")]
+    pub burn: Duration,
+}
+
+impl StageTimings {
+    pub fn total(&self) -> Duration {
+        self.download + self.crop + self.transcribe + self.burn
+    }
+
+    /// Stage-by-stage sum of several clips' timings
+    pub fn sum<'a>(timings: impl IntoIterator<Item = &'a StageTimings>) -> Self {
+        timings.into_iter().fold(Self::default(), |sum, t| Self {
+            download: sum.download + t.download,
+            crop: sum.crop + t.crop,
+            transcribe: sum.transcribe + t.transcribe,
+            burn: sum.burn + t.burn,
+        })
+    }
+}
+
+impl fmt::Display for StageTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "download {:.1}s, crop {:.1}s, transcribe {:.1}s, burn {:.1}s (total {:.1}s)",
+            self.download.as_secs_f64(),
+            self.crop.as_secs_f64(),
+            self.transcribe.as_secs_f64(),
+            self.burn.as_secs_f64(),
+            self.total().as_secs_f64()
+        )
+    }
+}
+
+/// Run `f`, adding the time it took to `stage`
+pub fn timed<T>(stage: &mut Duration, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    *stage += started.elapsed();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_timings() {
+        let clip = StageTimings {
+            download: Duration::from_millis(1500),
+            crop: Duration::from_secs(3),
+            transcribe: Duration::from_secs(10),
+            burn: Duration::from_secs(2),
+        };
+        let sum = StageTimings::sum(&[clip, clip]);
+        assert_eq!(sum.crop, Duration::from_secs(6));
+        assert_eq!(sum.total(), Duration::from_secs(33));
+        assert_eq!(
+            clip.to_string(),
+            "download 1.5s, crop 3.0s, transcribe 10.0s, burn 2.0s (total 16.5s)"
+        );
+        assert_eq!(
+            serde_json::to_value(clip).unwrap()["download"],
+            serde_json::json!(1.5)
+        );
+
+        let mut stage = Duration::ZERO;
+        assert_eq!(timed(&mut stage, || 7), 7);
+        assert!(stage < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// Progress notifications emitted while a run is processing clips
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// Work on clip `index` has started
+    ClipStarted { index: usize },
+    /// Encoding of clip `index` is `percent` (0–100) done
+    ClipProgress { index: usize, percent: f64 },
+    /// Clip `index` is finished, successfully or not
+    ClipFinished { index: usize, success: bool },
+}
+
+/// Callback receiving `ProgressEvent`s; cheap to clone
+#[derive(Clone)]
+pub struct ProgressSink(Arc<dyn Fn(ProgressEvent) + Send + Sync>);
+
+impl ProgressSink {
+    pub fn new(callback: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub fn emit(&self, event: ProgressEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
+
+/// Turn a line of ffmpeg `-progress` output into a percentage of `duration` seconds.
+///
+/// ffmpeg reports the encoded position as `out_time_us` and, despite the name, also in
+/// microseconds as `out_time_ms`. `progress=end` marks completion. Other lines yield `None`.
+pub fn parse_ffmpeg_progress(line: &str, duration: f64) -> Option<f64> {
+    let (key, value) = line.trim().split_once('=')?;
+    match key {
+        "out_time_us" | "out_time_ms" if duration > 0.0 => {
+            let micros: f64 = value.parse().ok()?;
+            Some((micros / 1_000_000.0 / duration * 100.0).clamp(0.0, 100.0))
+        }
+        "progress" if value == "end" => Some(100.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ffmpeg_progress() {
+        assert_eq!(parse_ffmpeg_progress("out_time_us=15000000", 30.0), Some(50.0));
+        assert_eq!(parse_ffmpeg_progress("out_time_ms=7500000\n", 30.0), Some(25.0));
+        // Reported before the first frame is written
+        assert_eq!(parse_ffmpeg_progress("out_time_us=N/A", 30.0), None);
+        assert_eq!(parse_ffmpeg_progress("out_time_us=-5000", 30.0), Some(0.0));
+        assert_eq!(parse_ffmpeg_progress("out_time_us=99000000", 30.0), Some(100.0));
+        assert_eq!(parse_ffmpeg_progress("progress=continue", 30.0), None);
+        assert_eq!(parse_ffmpeg_progress("progress=end", 30.0), Some(100.0));
+        assert_eq!(parse_ffmpeg_progress("frame=120", 30.0), None);
+    }
+}
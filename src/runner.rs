@@ -0,0 +1,588 @@
+use crate::error::Cancelled;
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Captured result of a finished command
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl CommandOutput {
+    /// Successful output with the given stdout
+    pub fn ok(stdout: &str) -> Self {
+        Self {
+            success: true,
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        }
+    }
+
+    /// Failed output with the given stderr
+    pub fn failed(stderr: &str) -> Self {
+        Self {
+            success: false,
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Executes external commands (ffmpeg, yt-dlp, whisper).
+///
+/// All pipeline stages go through this trait so tests can inspect the exact arguments
+/// without invoking real binaries.
+pub trait CommandRunner: Debug + Send + Sync {
+    /// Run with inherited stdio and return whether it succeeded.
+    ///
+    /// If `deadline` passes while the command is running, it is killed and `Ok(None)` is
+    /// returned.
+    fn run(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<bool>>;
+
+    /// Like `run`, but stdout is captured and passed to `on_line` one line at a time while
+    /// the command runs (used for ffmpeg `-progress pipe:1`).
+    fn run_with_progress(
+        &self,
+        cmd: &mut Command,
+        deadline: Option<Instant>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<Option<bool>> {
+        let _ = on_line;
+        self.run(cmd, deadline)
+    }
+
+    /// Run to completion, capturing stdout and stderr
+    fn output(&self, cmd: &mut Command) -> Result<CommandOutput>;
+
+    /// Like `output`, but the command is killed once `deadline` passes (`Ok(None)`)
+    fn output_until(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<CommandOutput>> {
+        let _ = deadline;
+        self.output(cmd).map(Some)
+    }
+
+    /// Whether the job using this runner was cancelled, so no further work should start
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// Spawn `cmd`, in a process group of its own when `own_group` is set (Unix only), so
+/// `kill_tree` also reaches what it starts: yt-dlp's ffmpeg, whisper's workers, ...
+///
+/// Only cancellable (server) jobs use their own group: it also keeps the terminal's Ctrl-C
+/// away from the child, which a CLI run relies on to stop everything.
+fn spawn(cmd: &mut Command, own_group: bool) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    if own_group {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = own_group;
+    cmd.spawn()
+}
+
+/// Kill `child`, and every process in its group if it leads one (see `spawn`)
+fn kill_tree(child: &mut Child, own_group: bool) {
+    #[cfg(unix)]
+    if own_group {
+        // SAFETY: kill(2) with a negative pid signals the group led by the child; it
+        // touches no memory
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = own_group;
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Spawn `cmd` and poll it until it exits.
+///
+/// The child is killed once `deadline` passes (`Ok(None)`) or `cancelled` is set
+/// (`Err(Cancelled)`); cancellable commands are killed along with their children. With
+/// `capture_stdout`, stdout is passed to `on_line` one line at a time; otherwise it is
+/// inherited.
+fn supervise(
+    cmd: &mut Command,
+    deadline: Option<Instant>,
+    cancelled: Option<&AtomicBool>,
+    capture_stdout: bool,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<Option<bool>> {
+    if capture_stdout {
+        cmd.stdout(Stdio::piped());
+    }
+    tracing::trace!("running {:?}", cmd);
+    let own_group = cancelled.is_some();
+    let mut child = spawn(cmd, own_group)?;
+
+    // Read on a separate thread so a quiet child doesn't block the deadline check
+    let reader = child.stdout.take().map(|stdout| {
+        let (sender, lines) = mpsc::channel();
+        let reader = std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        (reader, lines)
+    });
+
+    let result = loop {
+        if let Some((_, lines)) = &reader {
+            while let Ok(line) = lines.try_recv() {
+                on_line(&line);
+            }
+        }
+        if let Some(status) = child.try_wait()? {
+            break Ok(Some(status.success()));
+        }
+        let is_cancelled = cancelled.is_some_and(|flag| flag.load(Ordering::SeqCst));
+        if is_cancelled || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            kill_tree(&mut child, own_group);
+            break if is_cancelled { Err(Cancelled.into()) } else { Ok(None) };
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    if let Some((reader, lines)) = reader {
+        // A killed child's own children (yt-dlp's ffmpeg) can hold the pipe open long after
+        // it is gone, so the reader is only waited for when the child exited on its own
+        if matches!(result, Ok(Some(_))) {
+            let _ = reader.join();
+        }
+        for line in lines.try_iter() {
+            on_line(&line);
+        }
+    }
+    result
+}
+
+/// Runs commands on the real system
+#[derive(Debug, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<bool>> {
+        if deadline.is_none() {
+            tracing::trace!("running {:?}", cmd);
+            return Ok(Some(cmd.status()?.success()));
+        }
+        supervise(cmd, deadline, None, false, &mut |_| {})
+    }
+
+    fn run_with_progress(
+        &self,
+        cmd: &mut Command,
+        deadline: Option<Instant>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<Option<bool>> {
+        supervise(cmd, deadline, None, true, on_line)
+    }
+
+    fn output(&self, cmd: &mut Command) -> Result<CommandOutput> {
+        tracing::trace!("running {:?}", cmd);
+        let output = cmd.output()?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+        })
+    }
+
+    fn output_until(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<CommandOutput>> {
+        if deadline.is_none() {
+            return self.output(cmd).map(Some);
+        }
+        capture(cmd, deadline, None)
+    }
+}
+
+/// Runs commands on the real system until its job is cancelled.
+///
+/// `cancel` kills the command that is running, along with any processes it started (it
+/// fails with `Cancelled`), and makes every
+/// later command fail the same way before it starts, so a server job stops at its next step.
+#[derive(Debug, Default)]
+pub struct CancellableRunner {
+    cancelled: AtomicBool,
+}
+
+impl CancellableRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kill the running command and refuse new ones
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+}
+
+/// Spawn `cmd` with captured stdout and stderr and poll it until it exits, killing it like
+/// `supervise` does once `deadline` passes (`Ok(None)`) or `cancelled` is set
+fn capture(
+    cmd: &mut Command,
+    deadline: Option<Instant>,
+    cancelled: Option<&AtomicBool>,
+) -> Result<Option<CommandOutput>> {
+    tracing::trace!("running {:?}", cmd);
+    let own_group = cancelled.is_some();
+    let mut child = spawn(
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()),
+        own_group,
+    )?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if cancelled.is_some_and(|cancelled| cancelled.load(Ordering::SeqCst)) {
+            kill_tree(&mut child, own_group);
+            return Err(Cancelled.into());
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            kill_tree(&mut child, own_group);
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    Ok(Some(CommandOutput {
+        success: status.success(),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+/// Read a captured pipe to the end on its own thread
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+impl CommandRunner for CancellableRunner {
+    fn run(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<bool>> {
+        self.check()?;
+        supervise(cmd, deadline, Some(&self.cancelled), false, &mut |_| {})
+    }
+
+    fn run_with_progress(
+        &self,
+        cmd: &mut Command,
+        deadline: Option<Instant>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<Option<bool>> {
+        self.check()?;
+        supervise(cmd, deadline, Some(&self.cancelled), true, on_line)
+    }
+
+    fn output(&self, cmd: &mut Command) -> Result<CommandOutput> {
+        // Without a deadline the command always runs to completion
+        Ok(self.output_until(cmd, None)?.unwrap_or_default())
+    }
+
+    fn output_until(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<CommandOutput>> {
+        self.check()?;
+        capture(cmd, deadline, Some(&self.cancelled))
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs every command through `inner` with a shared `deadline`, for stages that call the
+/// runner without one of their own (transcription, burning subtitles).
+///
+/// A command still running at the deadline is killed; `output` then fails, and `expired`
+/// tells the caller the failure was the deadline.
+#[derive(Debug)]
+pub struct DeadlineRunner<'a> {
+    inner: &'a dyn CommandRunner,
+    deadline: Instant,
+    expired: AtomicBool,
+}
+
+impl<'a> DeadlineRunner<'a> {
+    pub fn new(inner: &'a dyn CommandRunner, deadline: Instant) -> Self {
+        Self {
+            inner,
+            deadline,
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether a command was killed (or refused) because the deadline passed
+    pub fn expired(&self) -> bool {
+        self.expired.load(Ordering::SeqCst)
+    }
+
+    /// The earlier of the caller's deadline and this runner's
+    fn earliest(&self, deadline: Option<Instant>) -> Option<Instant> {
+        Some(deadline.map_or(self.deadline, |deadline| deadline.min(self.deadline)))
+    }
+
+    fn note<T>(&self, result: Option<T>) -> Option<T> {
+        if result.is_none() {
+            self.expired.store(true, Ordering::SeqCst);
+        }
+        result
+    }
+}
+
+impl CommandRunner for DeadlineRunner<'_> {
+    fn run(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<bool>> {
+        let status = self.inner.run(cmd, self.earliest(deadline))?;
+        Ok(self.note(status))
+    }
+
+    fn run_with_progress(
+        &self,
+        cmd: &mut Command,
+        deadline: Option<Instant>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<Option<bool>> {
+        let status = self.inner.run_with_progress(cmd, self.earliest(deadline), on_line)?;
+        Ok(self.note(status))
+    }
+
+    fn output(&self, cmd: &mut Command) -> Result<CommandOutput> {
+        self.output_until(cmd, None)?.ok_or_else(|| anyhow::anyhow!("timed out"))
+    }
+
+    fn output_until(&self, cmd: &mut Command, deadline: Option<Instant>) -> Result<Option<CommandOutput>> {
+        let output = self.inner.output_until(cmd, self.earliest(deadline))?;
+        Ok(self.note(output))
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+}
+
+/// Test double that records every invocation and replays queued results.
+///
+/// When the queue is empty, commands succeed with empty output.
+#[derive(Debug, Default)]
+pub struct RecordingRunner {
+    calls: Mutex<Vec<Vec<String>>>,
+    responses: Mutex<VecDeque<CommandOutput>>,
+}
+
+impl RecordingRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the result for the next command
+    pub fn push_response(&self, output: CommandOutput) {
+        self.responses.lock().unwrap().push_back(output);
+    }
+
+    /// Every recorded invocation as `[program, args...]`
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, cmd: &Command) -> CommandOutput {
+        let mut call = vec![cmd.get_program().to_string_lossy().to_string()];
+        call.extend(cmd.get_args().map(|a| a.to_string_lossy().to_string()));
+        self.calls.lock().unwrap().push(call);
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| CommandOutput::ok(""))
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, cmd: &mut Command, _deadline: Option<Instant>) -> Result<Option<bool>> {
+        Ok(Some(self.record(cmd).success))
+    }
+
+    fn run_with_progress(
+        &self,
+        cmd: &mut Command,
+        _deadline: Option<Instant>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<Option<bool>> {
+        let output = self.record(cmd);
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            on_line(line);
+        }
+        Ok(Some(output.success))
+    }
+
+    fn output(&self, cmd: &mut Command) -> Result<CommandOutput> {
+        Ok(self.record(cmd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_system_runner_kills_on_deadline() {
+        let deadline = Some(Instant::now() + Duration::from_millis(200));
+        let started = Instant::now();
+        let result = SystemRunner
+            .run(Command::new("sleep").arg("5"), deadline)
+            .unwrap();
+        assert_eq!(result, None);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let result = SystemRunner.run(&mut Command::new("true"), None).unwrap();
+        assert_eq!(result, Some(true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_deadline_runner_kills_captured_commands() {
+        let runner = DeadlineRunner::new(&SystemRunner, Instant::now() + Duration::from_millis(200));
+        let output = runner.output(&mut Command::new("echo")).unwrap();
+        assert!(output.success);
+        assert!(!runner.expired());
+
+        let started = Instant::now();
+        assert!(runner.output(Command::new("sleep").arg("5")).is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(runner.expired());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_system_runner_streams_stdout_lines() {
+        let mut lines = Vec::new();
+        let result = SystemRunner
+            .run_with_progress(
+                Command::new("printf").arg("out_time_us=1\nprogress=end\n"),
+                None,
+                &mut |line| lines.push(line.to_string()),
+            )
+            .unwrap();
+        assert_eq!(result, Some(true));
+        assert_eq!(lines, vec!["out_time_us=1", "progress=end"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_system_runner_deadline_ignores_inherited_stdout() {
+        // The background sleep keeps stdout open after its parent shell is killed
+        let deadline = Some(Instant::now() + Duration::from_millis(200));
+        let started = Instant::now();
+        let mut lines = Vec::new();
+        let result = SystemRunner
+            .run_with_progress(
+                Command::new("sh").args(["-c", "sleep 5 & echo started; wait"]),
+                deadline,
+                &mut |line| lines.push(line.to_string()),
+            )
+            .unwrap();
+        assert_eq!(result, None);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(lines, vec!["started"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cancellable_runner_kills_running_command() {
+        let runner = std::sync::Arc::new(CancellableRunner::new());
+        let output = runner.output(Command::new("printf").arg("ok")).unwrap();
+        assert_eq!(output.stdout, b"ok");
+
+        let running = runner.clone();
+        let started = Instant::now();
+        let job = std::thread::spawn(move || running.run(Command::new("sleep").arg("5"), None));
+        std::thread::sleep(Duration::from_millis(200));
+        runner.cancel();
+
+        let error = job.join().unwrap().unwrap_err();
+        assert!(error.is::<Cancelled>());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Later commands don't start at all
+        let error = runner.output(&mut Command::new("true")).unwrap_err();
+        assert!(error.is::<Cancelled>());
+    }
+
+    /// A still-running process (zombies waiting to be reaped don't count)
+    #[cfg(target_os = "linux")]
+    fn is_running(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .is_ok_and(|stat| stat.split_whitespace().nth(2).is_none_or(|state| state != "Z"))
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cancel_kills_grandchildren() {
+        let pid_file = std::env::temp_dir().join(format!("yt-clipper-grandchild-{}", std::process::id()));
+        let runner = std::sync::Arc::new(CancellableRunner::new());
+        let running = runner.clone();
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let job = std::thread::spawn(move || running.run(Command::new("sh").args(["-c", &script]), None));
+        let started = Instant::now();
+        while !pid_file.exists() && started.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        let grandchild = std::fs::read_to_string(&pid_file).unwrap().trim().to_string();
+        let _ = std::fs::remove_file(&pid_file);
+        assert!(is_running(&grandchild));
+
+        runner.cancel();
+        assert!(job.join().unwrap().unwrap_err().is::<Cancelled>());
+        let killed = Instant::now();
+        while is_running(&grandchild) && killed.elapsed() < Duration::from_secs(2) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!is_running(&grandchild));
+    }
+
+    #[test]
+    fn test_recording_runner_replays_responses() {
+        let runner = RecordingRunner::new();
+        runner.push_response(CommandOutput::failed("boom"));
+
+        let first = runner.output(Command::new("yt-dlp").arg("-U")).unwrap();
+        assert!(!first.success);
+        assert_eq!(first.stderr, b"boom");
+
+        let second = runner.run(&mut Command::new("ffmpeg"), None).unwrap();
+        assert_eq!(second, Some(true));
+
+        assert_eq!(
+            runner.calls(),
+            vec![vec!["yt-dlp".to_string(), "-U".to_string()], vec!["ffmpeg".to_string()]]
+        );
+    }
+}
@@ -0,0 +1,790 @@
+use crate::subtitle::TimedWord;
+use crate::{HeatmapSegment, MAX_DURATION};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// Default distance (seconds) within which `DiverseWindows` suppresses weaker segments
+pub const DEFAULT_DIVERSITY_WINDOW: f64 = 60.0;
+
+/// Default share of the shorter clip two clips may have in common before they count as
+/// near-duplicates (see `overlap_fraction`)
+pub const DEFAULT_MAX_OVERLAP: f64 = 0.5;
+
+/// Highest rank `parse_rank_list` and `parse_rank_range` accept; no run has anywhere near
+/// this many segments, and it keeps a range like `1-100000000` from allocating every rank
+pub const MAX_RANK: usize = 1000;
+
+/// Seconds of silence kept before the first and after the last spoken word by
+/// `speech_bounds`, so clips don't cut into the breath or the word's tail
+pub const SPEECH_MARGIN: f64 = 0.3;
+
+/// How clips are chosen from the heatmap segments
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionStrategy {
+    /// Highest scores first, wherever they are in the video
+    #[default]
+    TopScore,
+    /// Highest scores first, skipping segments close to an already chosen one
+    DiverseWindows,
+}
+
+impl SelectionStrategy {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "top" | "top-score" | "top_score" | "topscore" => Some(SelectionStrategy::TopScore),
+            "diverse" | "diverse-windows" | "diverse_windows" | "diversewindows" => {
+                Some(SelectionStrategy::DiverseWindows)
+            }
+            _ => None,
+        }
+    }
+
+    /// Apply the strategy, returning segments in pick order
+    pub fn apply(self, segments: Vec<HeatmapSegment>, window: f64) -> Vec<HeatmapSegment> {
+        match self {
+            SelectionStrategy::TopScore => segments,
+            SelectionStrategy::DiverseWindows => suppress_nearby(&segments, window),
+        }
+    }
+}
+
+/// How the selected clips are numbered (`clip_1`, `clip_2`, ...)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputOrder {
+    /// In pick order, so `clip_1` is the hottest moment
+    #[default]
+    ByScore,
+    /// In the order they appear in the video, for compilations
+    Chronological,
+}
+
+impl OutputOrder {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "score" | "by-score" | "by_score" | "byscore" => Some(OutputOrder::ByScore),
+            "chronological" | "time" | "chrono" => Some(OutputOrder::Chronological),
+            _ => None,
+        }
+    }
+
+    /// Reorder `segments` (in pick order) for processing and numbering.
+    ///
+    /// Chronological order sorts the first `count` segments (all of them without a cap) by
+    /// start time; the rest stay behind them in pick order, as stand-ins for clips that fail.
+    pub fn apply(self, mut segments: Vec<HeatmapSegment>, count: Option<usize>) -> Vec<HeatmapSegment> {
+        if self == OutputOrder::Chronological {
+            let count = count.unwrap_or(segments.len()).min(segments.len());
+            segments[..count].sort_by(|a, b| a.start.total_cmp(&b.start));
+        }
+        segments
+    }
+}
+
+/// 1-based rank of each of `values` when sorted descending (ties keep their order)
+pub fn descending_ranks(values: &[f64]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+    let mut ranks = vec![0; values.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        ranks[i] = rank + 1;
+    }
+    ranks
+}
+
+/// Seconds at the start of a video that `ScoreWeighting::SkipIntro` treats as intro
+pub const INTRO_SECONDS: f64 = 30.0;
+
+/// Position-dependent multiplier applied to heatmap scores before selection.
+///
+/// The effective score of a segment is `score * factor(position)`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ScoreWeighting {
+    /// Use the heatmap intensity as is
+    #[default]
+    Flat,
+    /// Penalize segments in the first `INTRO_SECONDS`, ramping from 0.25x up to 1x
+    SkipIntro,
+    /// Favor later climaxes, ramping from 0.75x at the start to 1.25x at the end
+    LateClimax,
+}
+
+impl ScoreWeighting {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "flat" | "none" => Some(ScoreWeighting::Flat),
+            "skip-intro" | "skip_intro" | "skipintro" | "intro" => Some(ScoreWeighting::SkipIntro),
+            "late-climax" | "late_climax" | "lateclimax" | "late" => {
+                Some(ScoreWeighting::LateClimax)
+            }
+            _ => None,
+        }
+    }
+
+    /// Multiplier for a segment starting at `start` in a video of `total` seconds
+    pub fn factor(self, start: f64, total: f64) -> f64 {
+        match self {
+            ScoreWeighting::Flat => 1.0,
+            ScoreWeighting::SkipIntro => 0.25 + 0.75 * (start / INTRO_SECONDS).clamp(0.0, 1.0),
+            ScoreWeighting::LateClimax if total > 0.0 => {
+                0.75 + 0.5 * (start / total).clamp(0.0, 1.0)
+            }
+            ScoreWeighting::LateClimax => 1.0,
+        }
+    }
+
+    /// Replace each segment's score with its weighted score and sort by it, descending.
+    ///
+    /// `total` is the video length; the heatmap covers the whole video, so the end of the
+    /// last segment is used when it is not known.
+    pub fn apply(self, segments: &[HeatmapSegment], total: Option<f64>) -> Vec<HeatmapSegment> {
+        let total = total.unwrap_or_else(|| {
+            segments
+                .iter()
+                .map(|s| s.start + s.duration)
+                .fold(0.0, f64::max)
+        });
+
+        let mut weighted: Vec<HeatmapSegment> = segments
+            .iter()
+            .map(|s| HeatmapSegment {
+                score: s.score * self.factor(s.start, total),
+                ..s.clone()
+            })
+            .collect();
+        weighted.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        weighted
+    }
+}
+
+/// Merge segments whose padded ranges overlap or touch into a single segment.
+///
+/// YouTube's heatmap is made of many short, contiguous buckets, so once padding is
+/// applied, neighbouring hot buckets would produce clips with almost identical content.
+/// Merged segments span from the earliest start to the latest end and keep the highest
+/// score of their members. A segment that would stretch a merged one past `MAX_DURATION`
+/// starts a new one instead, so a long hot stretch is split rather than made into one
+/// overlong clip. The result is sorted by score descending.
+pub fn merge_segments(segments: &[HeatmapSegment], padding: f64) -> Vec<HeatmapSegment> {
+    let mut sorted: Vec<HeatmapSegment> = segments.to_vec();
+    sorted.sort_by(|a, b| {
+        a.start
+            .partial_cmp(&b.start)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut merged: Vec<HeatmapSegment> = Vec::new();
+
+    for segment in sorted {
+        if let Some(last) = merged.last_mut() {
+            let last_end = last.start + last.duration;
+            let end = last_end.max(segment.start + segment.duration);
+            if segment.start - padding <= last_end + padding && end - last.start <= MAX_DURATION {
+                last.duration = end - last.start;
+                last.score = last.score.max(segment.score);
+                continue;
+            }
+        }
+        merged.push(segment);
+    }
+
+    merged.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    merged
+}
+
+/// Greedily pick the highest-scoring segment, then drop every remaining segment that
+/// starts within `window` seconds of a picked segment's range, and repeat.
+///
+/// This spreads clips across the video instead of taking several from one hot region.
+/// The result is sorted by score descending.
+pub fn suppress_nearby(segments: &[HeatmapSegment], window: f64) -> Vec<HeatmapSegment> {
+    let mut candidates: Vec<HeatmapSegment> = segments.to_vec();
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut picked: Vec<HeatmapSegment> = Vec::new();
+    for candidate in candidates {
+        let is_near_picked = picked.iter().any(|p| {
+            candidate.start < p.start + p.duration + window
+                && candidate.start + candidate.duration > p.start - window
+        });
+        if !is_near_picked {
+            picked.push(candidate);
+        }
+    }
+
+    picked
+}
+
+/// Walk `segments` in order and drop any whose start lies within `min_gap` seconds of an
+/// already accepted segment's range.
+///
+/// Unlike `suppress_nearby`, the input order (the pick order) is kept. A `min_gap` of zero
+/// or less disables the filter.
+pub fn enforce_min_gap(segments: &[HeatmapSegment], min_gap: f64) -> Vec<HeatmapSegment> {
+    if min_gap <= 0.0 {
+        return segments.to_vec();
+    }
+
+    let mut accepted: Vec<HeatmapSegment> = Vec::new();
+    for segment in segments {
+        let too_close = accepted.iter().any(|a| {
+            segment.start >= a.start - min_gap && segment.start <= a.start + a.duration + min_gap
+        });
+        if !too_close {
+            accepted.push(segment.clone());
+        }
+    }
+    accepted
+}
+
+/// `count` segments of `length` seconds spread evenly over a video of `duration` seconds,
+/// each centered in its share of the video.
+///
+/// Stands in for the heatmap where there is none (local files); every segment scores 1.0.
+pub fn even_segments(duration: f64, count: usize, length: f64) -> Vec<HeatmapSegment> {
+    if count == 0 || duration <= 0.0 {
+        return Vec::new();
+    }
+    let share = duration / count as f64;
+    let length = length.min(share);
+    (0..count)
+        .map(|i| HeatmapSegment {
+            start: i as f64 * share + (share - length) / 2.0,
+            duration: length,
+            score: 1.0,
+        })
+        .collect()
+}
+
+/// Drop segments that start within the first `seconds` of the video.
+///
+/// Intros and sponsor rolls often get re-watched, so they score high without being
+/// highlights. A `seconds` of zero or less keeps everything.
+pub fn skip_intro(segments: &[HeatmapSegment], seconds: f64) -> Vec<HeatmapSegment> {
+    segments
+        .iter()
+        .filter(|segment| segment.start >= seconds)
+        .cloned()
+        .collect()
+}
+
+/// Keep only the segments at the given 1-based `ranks`, in rank order.
+///
+/// Returns the picked segments and any ranks that don't exist, so callers can warn about
+/// them instead of failing.
+pub fn pick_ranks(segments: &[HeatmapSegment], ranks: &[usize]) -> (Vec<HeatmapSegment>, Vec<usize>) {
+    let mut ranks = ranks.to_vec();
+    ranks.sort_unstable();
+    ranks.dedup();
+
+    let mut picked = Vec::new();
+    let mut missing = Vec::new();
+    for rank in ranks {
+        match rank.checked_sub(1).and_then(|i| segments.get(i)) {
+            Some(segment) => picked.push(segment.clone()),
+            None => missing.push(rank),
+        }
+    }
+    (picked, missing)
+}
+
+/// Parse a comma-separated rank list like `1,3,7`
+pub fn parse_rank_list(input: &str) -> Option<Vec<usize>> {
+    input
+        .split(',')
+        .map(parse_rank)
+        .collect()
+}
+
+/// Parse an inclusive rank range like `1-5`
+pub fn parse_rank_range(input: &str) -> Option<Vec<usize>> {
+    let (first, last) = input.split_once('-')?;
+    let (first, last) = (parse_rank(first)?, parse_rank(last)?);
+    (first <= last).then(|| (first..=last).collect())
+}
+
+/// A 1-based rank up to `MAX_RANK`
+fn parse_rank(input: &str) -> Option<usize> {
+    input.trim().parse().ok().filter(|&rank| (1..=MAX_RANK).contains(&rank))
+}
+
+/// Share of the shorter of two `(start, end)` ranges that the other one covers, from 0.0
+/// (disjoint or touching) to 1.0 (one contains the other)
+pub fn overlap_fraction(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let shared = a.1.min(b.1) - a.0.max(b.0);
+    let shorter = (a.1 - a.0).min(b.1 - b.0);
+    if shared <= 0.0 || shorter <= 0.0 {
+        return 0.0;
+    }
+    (shared / shorter).min(1.0)
+}
+
+/// Every pair of `ranges` overlapping by more than `max_overlap`, as
+/// `(first index, second index, overlap fraction)`
+pub fn find_overlaps(ranges: &[(f64, f64)], max_overlap: f64) -> Vec<(usize, usize, f64)> {
+    let mut overlaps = Vec::new();
+    for (i, &a) in ranges.iter().enumerate() {
+        for (j, &b) in ranges.iter().enumerate().skip(i + 1) {
+            let fraction = overlap_fraction(a, b);
+            if fraction > max_overlap {
+                overlaps.push((i, j, fraction));
+            }
+        }
+    }
+    overlaps
+}
+
+/// Drop segments whose clip overlaps a higher-scoring one by more than `max_overlap`,
+/// keeping the input order. Returns the kept and the dropped segments.
+///
+/// `clip_range` gives the padded `(start, end)` of the clip a segment becomes.
+pub fn reject_overlaps(
+    segments: &[HeatmapSegment],
+    max_overlap: f64,
+    clip_range: impl Fn(&HeatmapSegment) -> (f64, f64),
+) -> (Vec<HeatmapSegment>, Vec<HeatmapSegment>) {
+    let mut by_score: Vec<usize> = (0..segments.len()).collect();
+    by_score.sort_by(|&a, &b| segments[b].score.total_cmp(&segments[a].score));
+
+    let mut kept_ranges = Vec::new();
+    let mut keep = vec![false; segments.len()];
+    for i in by_score {
+        let range = clip_range(&segments[i]);
+        if kept_ranges
+            .iter()
+            .all(|&kept| overlap_fraction(range, kept) <= max_overlap)
+        {
+            kept_ranges.push(range);
+            keep[i] = true;
+        }
+    }
+
+    let (kept, dropped): (Vec<_>, Vec<_>) = segments.iter().zip(keep).partition(|(_, keep)| *keep);
+    (
+        kept.into_iter().map(|(segment, _)| segment.clone()).collect(),
+        dropped.into_iter().map(|(segment, _)| segment.clone()).collect(),
+    )
+}
+
+/// Take segments in order (best first) while their total clip length fits in `budget`
+/// seconds, stopping at the first one that would exceed it.
+///
+/// `clip_length` gives the padded length of the clip a segment becomes. Stopping rather
+/// than skipping ahead keeps the budget from favouring short, weaker moments.
+pub fn take_within_budget(
+    segments: &[HeatmapSegment],
+    budget: f64,
+    clip_length: impl Fn(&HeatmapSegment) -> f64,
+) -> Vec<HeatmapSegment> {
+    let mut total = 0.0;
+    segments
+        .iter()
+        .take_while(|segment| {
+            total += clip_length(segment);
+            total <= budget
+        })
+        .cloned()
+        .collect()
+}
+
+/// Parse a comma-separated keyword list like `rust,cargo build`, dropping empty entries
+pub fn parse_keywords(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|keyword| keyword.trim().to_string())
+        .filter(|keyword| !keyword.is_empty())
+        .collect()
+}
+
+/// Lowercase `text` and drop punctuation, so "Rust," matches the keyword "rust"
+fn normalize_word(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// One segment per place a keyword is spoken, spanning the matched words.
+///
+/// Keywords of several words match consecutive transcript words. Matching ignores case
+/// and punctuation. Every match scores 1.0, so segments stay in transcript order once
+/// merged.
+pub fn keyword_segments(words: &[TimedWord], keywords: &[String]) -> Vec<HeatmapSegment> {
+    let normalized: Vec<String> = words.iter().map(|w| normalize_word(&w.text)).collect();
+    let phrases: Vec<Vec<String>> = keywords
+        .iter()
+        .map(|k| k.split_whitespace().map(normalize_word).filter(|w| !w.is_empty()).collect())
+        .filter(|phrase: &Vec<String>| !phrase.is_empty())
+        .collect();
+
+    let mut segments = Vec::new();
+    for start in 0..words.len() {
+        for phrase in &phrases {
+            let end = start + phrase.len();
+            if end <= words.len() && normalized[start..end] == phrase[..] {
+                segments.push(HeatmapSegment {
+                    start: words[start].start,
+                    duration: (words[end - 1].end - words[start].start).max(0.0),
+                    score: 1.0,
+                });
+            }
+        }
+    }
+    segments
+}
+
+/// The part of a `duration`-second clip from its first to its last spoken word (plus
+/// `SPEECH_MARGIN`), with `words` timed from the clip's start.
+///
+/// A range shorter than `min_duration` is widened evenly around the speech, within the
+/// clip. Returns `None` when nothing is said, so the clip keeps its full range.
+pub fn speech_bounds(words: &[TimedWord], duration: f64, min_duration: f64) -> Option<(f64, f64)> {
+    let spoken: Vec<&TimedWord> = words
+        .iter()
+        .filter(|word| !word.text.trim().is_empty() && word.end > 0.0 && word.start < duration)
+        .collect();
+    if spoken.is_empty() {
+        return None;
+    }
+    let first = spoken.iter().map(|word| word.start).fold(f64::INFINITY, f64::min);
+    let last = spoken.iter().map(|word| word.end).fold(f64::NEG_INFINITY, f64::max);
+
+    let mut start = (first - SPEECH_MARGIN).max(0.0);
+    let mut end = (last + SPEECH_MARGIN).min(duration);
+    let missing = min_duration.min(duration) - (end - start);
+    if missing > 0.0 {
+        start -= missing / 2.0;
+        end += missing / 2.0;
+        // Shift back inside the clip when one side ran over
+        if start < 0.0 {
+            end -= start;
+            start = 0.0;
+        }
+        if end > duration {
+            start -= end - duration;
+            end = duration;
+        }
+    }
+    Some((start.max(0.0), end.min(duration)))
+}
+
+/// `words` within `start..end`, retimed from `start` (and clamped to the range), for
+/// subtitling the trimmed clip with the words found while trimming it
+pub fn words_in_range(words: &[TimedWord], start: f64, end: f64) -> Vec<TimedWord> {
+    words
+        .iter()
+        .filter(|word| word.end > start && word.start < end)
+        .map(|word| TimedWord {
+            text: word.text.clone(),
+            start: (word.start - start).max(0.0),
+            end: (word.end.min(end) - start).max(0.0),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f64, duration: f64, score: f64) -> HeatmapSegment {
+        HeatmapSegment {
+            start,
+            duration,
+            score,
+        }
+    }
+
+    #[test]
+    fn test_skip_intro_weighting() {
+        let weighting = ScoreWeighting::SkipIntro;
+        assert_eq!(weighting.factor(0.0, 600.0), 0.25);
+        assert_eq!(weighting.factor(15.0, 600.0), 0.625);
+        assert_eq!(weighting.factor(INTRO_SECONDS, 600.0), 1.0);
+        assert_eq!(weighting.factor(300.0, 600.0), 1.0);
+
+        let segments = vec![seg(5.0, 5.0, 0.9), seg(200.0, 5.0, 0.6)];
+        let weighted = weighting.apply(&segments, Some(600.0));
+        assert_eq!(weighted[0].start, 200.0);
+        assert_eq!(weighted[0].score, 0.6);
+        assert!((weighted[1].score - 0.9 * 0.375).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_late_climax_weighting() {
+        let weighting = ScoreWeighting::LateClimax;
+        assert_eq!(weighting.factor(0.0, 400.0), 0.75);
+        assert_eq!(weighting.factor(200.0, 400.0), 1.0);
+        assert_eq!(weighting.factor(400.0, 400.0), 1.25);
+
+        // Without a known length, the heatmap's end stands in for it
+        let segments = vec![seg(10.0, 10.0, 0.8), seg(390.0, 10.0, 0.7)];
+        let weighted = weighting.apply(&segments, None);
+        assert_eq!(weighted[0].start, 390.0);
+
+        let flat = ScoreWeighting::Flat.apply(&segments, None);
+        assert_eq!(flat[0].score, 0.8);
+        assert_eq!(ScoreWeighting::from_input("skip-intro"), Some(ScoreWeighting::SkipIntro));
+        assert_eq!(ScoreWeighting::from_input("bogus"), None);
+    }
+
+    #[test]
+    fn test_merge_adjacent_segments() {
+        let segments = vec![seg(100.0, 5.0, 0.6), seg(105.0, 5.0, 0.9), seg(300.0, 5.0, 0.5)];
+        let merged = merge_segments(&segments, 10.0);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].start, 100.0);
+        assert_eq!(merged[0].duration, 10.0);
+        assert_eq!(merged[0].score, 0.9);
+        assert_eq!(merged[1].start, 300.0);
+    }
+
+    #[test]
+    fn test_merge_respects_padding() {
+        // 15s apart: merged with 10s padding on each side, separate without padding
+        let segments = vec![seg(0.0, 5.0, 0.5), seg(20.0, 5.0, 0.7)];
+        assert_eq!(merge_segments(&segments, 10.0).len(), 1);
+        assert_eq!(merge_segments(&segments, 0.0).len(), 2);
+    }
+
+    #[test]
+    fn test_merge_splits_at_max_duration() {
+        // 100 contiguous seconds of 5s buckets
+        let segments: Vec<HeatmapSegment> = (0..20).map(|i| seg(i as f64 * 5.0, 5.0, 0.5)).collect();
+        let mut merged = merge_segments(&segments, 10.0);
+        merged.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
+
+        let ranges: Vec<(f64, f64)> = merged.iter().map(|s| (s.start, s.duration)).collect();
+        assert_eq!(ranges, vec![(0.0, MAX_DURATION), (60.0, 40.0)]);
+    }
+
+    #[test]
+    fn test_suppress_nearby() {
+        let segments = vec![
+            seg(100.0, 5.0, 0.9),
+            seg(120.0, 5.0, 0.8),
+            seg(400.0, 5.0, 0.7),
+            seg(170.0, 5.0, 0.6),
+        ];
+        let picked = suppress_nearby(&segments, 60.0);
+
+        let starts: Vec<f64> = picked.iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![100.0, 400.0, 170.0]);
+    }
+
+    #[test]
+    fn test_selection_strategy() {
+        let segments = vec![seg(100.0, 5.0, 0.9), seg(110.0, 5.0, 0.8)];
+        assert_eq!(SelectionStrategy::TopScore.apply(segments.clone(), 60.0).len(), 2);
+        assert_eq!(SelectionStrategy::DiverseWindows.apply(segments, 60.0).len(), 1);
+        assert_eq!(
+            SelectionStrategy::from_input("diverse"),
+            Some(SelectionStrategy::DiverseWindows)
+        );
+        assert_eq!(SelectionStrategy::DiverseWindows.to_string(), "diverse-windows");
+    }
+
+    #[test]
+    fn test_pick_ranks() {
+        let segments = vec![seg(10.0, 5.0, 0.9), seg(50.0, 5.0, 0.8), seg(90.0, 5.0, 0.7)];
+        let (picked, missing) = pick_ranks(&segments, &[3, 1, 7, 1]);
+
+        let starts: Vec<f64> = picked.iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![10.0, 90.0]);
+        assert_eq!(missing, vec![7]);
+    }
+
+    #[test]
+    fn test_parse_ranks() {
+        assert_eq!(parse_rank_list("1,3, 7"), Some(vec![1, 3, 7]));
+        assert_eq!(parse_rank_list("1,0"), None);
+        assert_eq!(parse_rank_list("1,x"), None);
+        assert_eq!(parse_rank_range("2-4"), Some(vec![2, 3, 4]));
+        assert_eq!(parse_rank_range("4-2"), None);
+        assert_eq!(parse_rank_range("0-2"), None);
+        assert_eq!(parse_rank_range("3"), None);
+        assert_eq!(parse_rank_range("1-1000").map(|ranks| ranks.len()), Some(MAX_RANK));
+        assert_eq!(parse_rank_range("1-100000000"), None);
+        assert_eq!(parse_rank_list("1,1001"), None);
+    }
+
+    #[test]
+    fn test_enforce_min_gap() {
+        let segments = vec![
+            seg(100.0, 10.0, 0.9),
+            seg(125.0, 5.0, 0.8),
+            seg(80.0, 5.0, 0.7),
+            seg(140.0, 5.0, 0.6),
+        ];
+
+        let starts = |segments: Vec<HeatmapSegment>| -> Vec<f64> {
+            segments.iter().map(|s| s.start).collect()
+        };
+        assert_eq!(starts(enforce_min_gap(&segments, 20.0)), vec![100.0, 140.0]);
+        assert_eq!(starts(enforce_min_gap(&segments, 10.0)), vec![100.0, 125.0, 80.0]);
+        assert_eq!(enforce_min_gap(&segments, 0.0).len(), 4);
+    }
+
+    #[test]
+    fn test_overlap_fraction() {
+        assert_eq!(overlap_fraction((0.0, 10.0), (5.0, 15.0)), 0.5);
+        assert_eq!(overlap_fraction((0.0, 10.0), (10.0, 20.0)), 0.0);
+        assert_eq!(overlap_fraction((0.0, 10.0), (30.0, 40.0)), 0.0);
+        // Measured against the shorter clip, so containment is a full overlap
+        assert_eq!(overlap_fraction((0.0, 40.0), (10.0, 20.0)), 1.0);
+        assert_eq!(overlap_fraction((10.0, 20.0), (0.0, 40.0)), 1.0);
+
+        let ranges = [(0.0, 10.0), (2.0, 12.0), (11.0, 21.0)];
+        assert_eq!(find_overlaps(&ranges, 0.5), vec![(0, 1, 0.8)]);
+        assert_eq!(find_overlaps(&ranges, 0.0).len(), 2);
+    }
+
+    #[test]
+    fn test_reject_overlaps() {
+        let segments = vec![seg(100.0, 10.0, 0.6), seg(104.0, 10.0, 0.9), seg(200.0, 10.0, 0.5)];
+        let range = |s: &HeatmapSegment| (s.start, s.start + s.duration);
+        let (kept, dropped) = reject_overlaps(&segments, 0.5, range);
+        let starts: Vec<f64> = kept.iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![104.0, 200.0]);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].start, 100.0);
+
+        let (kept, _) = reject_overlaps(&segments, 0.7, range);
+        assert_eq!(kept.len(), 3);
+    }
+
+    #[test]
+    fn test_even_segments() {
+        let segments = even_segments(300.0, 3, 30.0);
+        let starts: Vec<f64> = segments.iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![35.0, 135.0, 235.0]);
+        assert!(segments.iter().all(|s| s.duration == 30.0 && s.score == 1.0));
+
+        // Short videos get shorter clips rather than overlapping ones
+        let segments = even_segments(40.0, 4, 30.0);
+        assert_eq!(segments[1].start, 10.0);
+        assert_eq!(segments[1].duration, 10.0);
+        assert!(even_segments(0.0, 3, 30.0).is_empty());
+    }
+
+    #[test]
+    fn test_skip_intro() {
+        let segments = vec![seg(0.0, 5.0, 1.0), seg(25.0, 5.0, 0.9), seg(30.0, 5.0, 0.8)];
+        let starts: Vec<f64> = skip_intro(&segments, 30.0).iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![30.0]);
+        assert_eq!(skip_intro(&segments, 0.0).len(), 3);
+    }
+
+    #[test]
+    fn test_output_order() {
+        let segments = vec![seg(300.0, 5.0, 0.9), seg(100.0, 5.0, 0.8), seg(200.0, 5.0, 0.7), seg(50.0, 5.0, 0.6)];
+        let starts = |segments: &[HeatmapSegment]| segments.iter().map(|s| s.start).collect::<Vec<_>>();
+
+        assert_eq!(starts(&OutputOrder::ByScore.apply(segments.clone(), Some(3))), vec![300.0, 100.0, 200.0, 50.0]);
+        // Only the clips that will be made are sorted; the fallback stays last
+        assert_eq!(
+            starts(&OutputOrder::Chronological.apply(segments.clone(), Some(3))),
+            vec![100.0, 200.0, 300.0, 50.0]
+        );
+        assert_eq!(
+            starts(&OutputOrder::Chronological.apply(segments.clone(), None)),
+            vec![50.0, 100.0, 200.0, 300.0]
+        );
+        assert_eq!(descending_ranks(&[0.7, 0.9, 0.8, 0.9]), vec![4, 1, 3, 2]);
+        assert_eq!(OutputOrder::from_input("Chronological"), Some(OutputOrder::Chronological));
+    }
+
+    #[test]
+    fn test_speech_bounds() {
+        let word = |start: f64, end: f64| TimedWord {
+            text: "word".to_string(),
+            start,
+            end,
+        };
+        let words = vec![word(4.0, 4.5), word(5.0, 6.0), word(20.0, 21.0)];
+        let (start, end) = speech_bounds(&words, 30.0, 3.0).unwrap();
+        assert!((start - 3.7).abs() < 1e-9 && (end - 21.3).abs() < 1e-9);
+
+        // Widened to the minimum around a short utterance, and kept inside the clip
+        let (start, end) = speech_bounds(&[word(10.0, 10.4)], 30.0, 3.0).unwrap();
+        assert!((end - start - 3.0).abs() < 1e-9);
+        assert!(start < 9.7 && end > 10.7);
+        assert_eq!(speech_bounds(&[word(0.0, 0.4)], 30.0, 3.0), Some((0.0, 3.0)));
+        assert_eq!(speech_bounds(&[word(29.8, 30.0)], 30.0, 3.0), Some((27.0, 30.0)));
+
+        assert_eq!(speech_bounds(&[], 30.0, 3.0), None);
+    }
+
+    #[test]
+    fn test_words_in_range() {
+        let word = |start: f64, end: f64| TimedWord {
+            text: "word".to_string(),
+            start,
+            end,
+        };
+        let words = vec![word(1.0, 2.0), word(3.5, 4.5), word(6.0, 7.0), word(9.5, 10.5)];
+        let kept = words_in_range(&words, 3.7, 10.0);
+        let times: Vec<(f64, f64)> = kept.iter().map(|w| (w.start, w.end)).collect();
+        let expected = [(0.0, 0.8), (2.3, 3.3), (5.8, 6.3)];
+        assert_eq!(times.len(), expected.len());
+        for ((start, end), (want_start, want_end)) in times.iter().zip(expected) {
+            assert!((start - want_start).abs() < 1e-9 && (end - want_end).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_keyword_segments() {
+        let word = |text: &str, start: f64| TimedWord {
+            text: text.to_string(),
+            start,
+            end: start + 0.5,
+        };
+        let words = vec![
+            word("We", 1.0),
+            word("love", 1.5),
+            word("Rust!", 2.0),
+            word("Cargo", 40.0),
+            word("build,", 40.5),
+            word("rust", 90.0),
+        ];
+        let keywords = parse_keywords("rust, cargo build ,");
+        assert_eq!(keywords, ["rust", "cargo build"]);
+
+        let found = keyword_segments(&words, &keywords);
+        let spans: Vec<(f64, f64)> = found.iter().map(|s| (s.start, s.duration)).collect();
+        assert_eq!(spans, [(2.0, 0.5), (40.0, 1.0), (90.0, 0.5)]);
+        assert!(keyword_segments(&words, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_take_within_budget() {
+        let segments = vec![seg(100.0, 40.0, 0.9), seg(10.0, 60.0, 0.8), seg(300.0, 20.0, 0.7)];
+        let length = |s: &HeatmapSegment| s.duration + 20.0;
+
+        let starts = |picked: Vec<HeatmapSegment>| picked.iter().map(|s| s.start).collect::<Vec<_>>();
+        assert_eq!(starts(take_within_budget(&segments, 170.0, length)), [100.0, 10.0]);
+        assert_eq!(starts(take_within_budget(&segments, 60.0, length)), [100.0]);
+        // Stops at the first clip that doesn't fit instead of skipping to a weaker one
+        assert_eq!(starts(take_within_budget(&segments, 100.0, length)), [100.0]);
+        assert!(take_within_budget(&segments, 30.0, length).is_empty());
+        assert_eq!(take_within_budget(&segments, 1000.0, length).len(), 3);
+    }
+}
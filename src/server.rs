@@ -1,57 +1,733 @@
 use axum::{
-    extract::Json,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::post,
+    extract::{ConnectInfo, Json, Path as UrlPath, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
     Router,
 };
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
 use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
 use yt_clipper_rust::{
-    full_process, subtitle::check_python_available, CropMode, ProcessOptions, SubtitleConfig,
-    WhisperModel,
+    default_temp_dir,
+    deps::{detect_versions, DependencyVersions},
+    extract_video_id, fetch_heatmap, full_process, get_duration_until,
+    subtitle::{check_python_available, parse_language, SubtitlePosition},
+    CancellableRunner, Cancelled, ClipperError, CropMode, HeatmapSegment, NoHeatmap, OutputSpec, PreviewFormat, ProcessOptions, ProgressEvent, ProgressSink, StageTimings, SubtitleConfig, SubtitlePreset,
+    Throttle, VideoFormat, Warning, WhisperModel, DEFAULT_CRF, MAX_CLIPS, MAX_CRF, PADDING,
 };
 use std::net::SocketAddr;
 
-#[derive(Deserialize)]
+/// Environment variable holding the API token clients must send as `Authorization: Bearer`
+pub const API_TOKEN_ENV: &str = "YT_CLIPPER_API_TOKEN";
+
+/// Limits on how much work clients can make the server do, and who may ask for it
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Directory served under `/clips`; per-request `output_dir` is resolved inside it
+    pub clips_root: PathBuf,
+    /// Jobs processed at the same time; further jobs wait for a free slot
+    pub max_concurrent_jobs: usize,
+    /// Process requests allowed per client IP per minute
+    pub requests_per_minute: u32,
+    /// Token required on job endpoints; `None` disables authentication
+    pub api_token: Option<String>,
+    /// ffmpeg binary for every job (see `deps::ffmpeg_program`)
+    pub ffmpeg_path: Option<PathBuf>,
+    /// yt-dlp binary for every job (see `deps::ytdlp_program`)
+    pub ytdlp_path: Option<PathBuf>,
+    /// Proxy for every request to YouTube (`--proxy`); clients can't pick their own, so
+    /// the server can't be used to reach arbitrary hosts
+    pub proxy: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            clips_root: PathBuf::from("clips"),
+            max_concurrent_jobs: 2,
+            requests_per_minute: 10,
+            api_token: None,
+            ffmpeg_path: None,
+            ytdlp_path: None,
+            proxy: None,
+        }
+    }
+}
+
+/// Fixed-window request counter per client IP
+#[derive(Debug)]
+struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    clients: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request from `ip`, returning how long to wait if it is over the limit
+    fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+
+        let (start, count) = clients.entry(ip).or_insert((now, 0));
+        if *count >= self.limit {
+            return Err(self.window - now.duration_since(*start));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// How long `/api/health` reuses the detected tool versions before running the tools again
+const TOOLS_TTL: Duration = Duration::from_secs(300);
+
+/// Versions and capabilities of the external tools, as reported by `/api/health`
+#[derive(Debug, Clone, Default)]
+struct ToolStatus {
+    versions: DependencyVersions,
+    python: bool,
+}
+
+/// The last `ToolStatus` detected. Detecting runs ffmpeg, yt-dlp and python, so it isn't
+/// redone on every health check
+#[derive(Debug, Default)]
+struct ToolCache {
+    detected: Mutex<Option<(Instant, ToolStatus)>>,
+}
+
+impl ToolCache {
+    /// The cached status, unless it is older than `TOOLS_TTL`
+    fn get(&self, now: Instant) -> Option<ToolStatus> {
+        let detected = self.detected.lock().unwrap();
+        detected
+            .as_ref()
+            .filter(|(at, _)| now.duration_since(*at) < TOOLS_TTL)
+            .map(|(_, status)| status.clone())
+    }
+
+    fn store(&self, now: Instant, status: ToolStatus) {
+        *self.detected.lock().unwrap() = Some((now, status));
+    }
+}
+
+/// Where a server job is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum JobStatus {
+    /// Waiting for a free job slot
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// How long `/api/validate` waits for yt-dlp to report the duration
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Finished jobs remembered for `GET /api/jobs`; older ones are forgotten
+const FINISHED_JOBS_KEPT: usize = 50;
+
+/// Prefix of generated job ids, which client-chosen ids can't start with
+const GENERATED_ID_PREFIX: &str = "auto-";
+
+/// How often a queued job checks whether the throttle lets it start
+const THROTTLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Progress events buffered per job; a client further behind skips the oldest
+const PROGRESS_EVENTS_BUFFERED: usize = 256;
+
+#[derive(Debug)]
+struct Job {
+    id: String,
+    url: String,
+    status: JobStatus,
+    /// Runs every command of the job, so cancelling it kills the ffmpeg/yt-dlp child
+    runner: Arc<CancellableRunner>,
+    /// The spawned processing task, from when it exists until it has wound down
+    task: Option<AbortHandle>,
+    /// Per-job downloads and intermediates, removed once the job has stopped
+    temp_dir: PathBuf,
+    /// Publishes the job's progress to `GET /api/jobs/:id/events`, until the job stops
+    events: Option<broadcast::Sender<ProgressEvent>>,
+}
+
+/// Why a job could not be cancelled
+#[derive(Debug, PartialEq)]
+enum CancelError {
+    NotFound,
+    Finished(JobStatus),
+}
+
+/// Why `JobRegistry::register` refused a job
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RegisterError {
+    /// The requested `job_id` can't be used as an id
+    InvalidId(String),
+    /// A job with the requested id is still queued, running or winding down
+    InProgress(String),
+}
+
+impl RegisterError {
+    fn status(&self) -> StatusCode {
+        match self {
+            RegisterError::InvalidId(_) => StatusCode::BAD_REQUEST,
+            RegisterError::InProgress(_) => StatusCode::CONFLICT,
+        }
+    }
+}
+
+impl std::fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterError::InvalidId(reason) => write!(f, "{}", reason),
+            RegisterError::InProgress(id) => write!(f, "Job {} is already in progress", id),
+        }
+    }
+}
+
+/// Jobs submitted to this server, oldest first
+#[derive(Debug, Default)]
+struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<Vec<Job>>,
+}
+
+impl JobRegistry {
+    /// Add a queued job, using `requested_id` if the client chose one.
+    ///
+    /// Fails if a job with that id is still queued or running, or was cancelled but is
+    /// still winding down.
+    fn register(
+        &self,
+        requested_id: Option<&str>,
+        url: &str,
+    ) -> Result<(String, Arc<CancellableRunner>, PathBuf), RegisterError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let id = match requested_id.map(str::trim).filter(|id| !id.is_empty()) {
+            Some(id) if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') => {
+                return Err(RegisterError::InvalidId(
+                    "job_id may only contain letters, digits, '-' and '_'".to_string(),
+                ));
+            }
+            Some(id) if id.starts_with(GENERATED_ID_PREFIX) => {
+                return Err(RegisterError::InvalidId(format!(
+                    "job_id can't start with '{}'",
+                    GENERATED_ID_PREFIX
+                )));
+            }
+            Some(id) => id.to_string(),
+            None => format!("{}{}", GENERATED_ID_PREFIX, self.next_id.fetch_add(1, Ordering::SeqCst) + 1),
+        };
+        if jobs
+            .iter()
+            .any(|job| job.id == id && (!job.status.is_finished() || job.task.is_some()))
+        {
+            return Err(RegisterError::InProgress(id));
+        }
+
+        jobs.retain(|job| job.id != id);
+        if jobs.iter().filter(|job| job.status.is_finished()).count() >= FINISHED_JOBS_KEPT {
+            if let Some(oldest) = jobs.iter().position(|job| job.status.is_finished()) {
+                jobs.remove(oldest);
+            }
+        }
+
+        let runner = Arc::new(CancellableRunner::new());
+        let temp_dir = default_temp_dir().join(format!("job-{}", id));
+        jobs.push(Job {
+            id: id.clone(),
+            url: url.to_string(),
+            status: JobStatus::Queued,
+            runner: runner.clone(),
+            task: None,
+            temp_dir: temp_dir.clone(),
+            events: Some(broadcast::channel(PROGRESS_EVENTS_BUFFERED).0),
+        });
+        Ok((id, runner, temp_dir))
+    }
+
+    /// Sink publishing the job's progress to its event subscribers
+    fn progress_sink(&self, id: &str) -> Option<ProgressSink> {
+        let events = self.with_job(id, |job| job.events.clone()).flatten()?;
+        Some(ProgressSink::new(move |event| {
+            // Nobody may be listening
+            let _ = events.send(event);
+        }))
+    }
+
+    /// Follow the progress of a job that hasn't stopped yet
+    fn subscribe(&self, id: &str) -> Option<Option<broadcast::Receiver<ProgressEvent>>> {
+        self.with_job(id, |job| job.events.as_ref().map(broadcast::Sender::subscribe))
+    }
+
+    fn with_job<T>(&self, id: &str, f: impl FnOnce(&mut Job) -> T) -> Option<T> {
+        self.jobs.lock().unwrap().iter_mut().find(|job| job.id == id).map(f)
+    }
+
+    #[cfg(test)]
+    fn status(&self, id: &str) -> Option<JobStatus> {
+        self.with_job(id, |job| job.status)
+    }
+
+    fn count(&self, status: JobStatus) -> usize {
+        self.jobs.lock().unwrap().iter().filter(|job| job.status == status).count()
+    }
+
+    /// Attach the job's task, or abort it if the job was cancelled before it had one
+    fn set_task(&self, id: &str, task: AbortHandle) {
+        self.with_job(id, |job| {
+            if job.status == JobStatus::Cancelled {
+                task.abort();
+            } else {
+                job.task = Some(task);
+            }
+        });
+    }
+
+    /// Mark a queued job as running. Returns `false` if it was cancelled while waiting.
+    fn start(&self, id: &str) -> bool {
+        self.with_job(id, |job| {
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Running;
+            }
+            job.status == JobStatus::Running
+        })
+        .unwrap_or(false)
+    }
+
+    /// Record how the job ended and remove its temp files.
+    ///
+    /// A job that was cancelled stays cancelled, however its task wound down.
+    fn finish(&self, id: &str, status: JobStatus) {
+        let temp_dir = self.with_job(id, |job| {
+            if job.status != JobStatus::Cancelled {
+                job.status = status;
+            }
+            job.task = None;
+            // Event streams end once the job's own sink is dropped too
+            job.events = None;
+            job.temp_dir.clone()
+        });
+        if let Some(temp_dir) = temp_dir {
+            let _ = std::fs::remove_dir_all(temp_dir);
+        }
+    }
+
+    /// Stop a queued or running job.
+    ///
+    /// A queued job's task is aborted before it touches any file. A running job has its
+    /// child processes killed and fails at its next step; its task then calls `finish`,
+    /// which removes the temp files once nothing writes to them anymore.
+    fn cancel(&self, id: &str) -> Result<(), CancelError> {
+        let queued_temp_dir = self
+            .with_job(id, |job| {
+                if job.status.is_finished() {
+                    return Err(CancelError::Finished(job.status));
+                }
+                let queued = job.status == JobStatus::Queued;
+                job.status = JobStatus::Cancelled;
+                job.runner.cancel();
+                job.events = None;
+                if !queued {
+                    return Ok(None);
+                }
+                if let Some(task) = job.task.take() {
+                    task.abort();
+                }
+                Ok(Some(job.temp_dir.clone()))
+            })
+            .ok_or(CancelError::NotFound)??;
+        if let Some(temp_dir) = queued_temp_dir {
+            let _ = std::fs::remove_dir_all(temp_dir);
+        }
+        Ok(())
+    }
+
+    fn list(&self) -> Vec<JobSummary> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|job| JobSummary {
+                id: job.id.clone(),
+                url: job.url.clone(),
+                status: job.status,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct JobSummary {
+    id: String,
+    url: String,
+    status: JobStatus,
+}
+
+struct AppState {
+    clips_root: PathBuf,
+    ffmpeg_path: Option<PathBuf>,
+    ytdlp_path: Option<PathBuf>,
+    proxy: Option<String>,
+    api_token: Option<String>,
+    jobs: Semaphore,
+    max_jobs: usize,
+    /// Shared by every job, so they back off together when YouTube throttles
+    throttle: Arc<Throttle>,
+    registry: JobRegistry,
+    limiter: RateLimiter,
+    tools: ToolCache,
+}
+
+impl AppState {
+    fn new(config: &ServerConfig) -> Self {
+        Self {
+            clips_root: config.clips_root.clone(),
+            ffmpeg_path: config.ffmpeg_path.clone(),
+            ytdlp_path: config.ytdlp_path.clone(),
+            proxy: config.proxy.clone(),
+            api_token: config.api_token.clone().filter(|token| !token.is_empty()),
+            jobs: Semaphore::new(config.max_concurrent_jobs.max(1)),
+            max_jobs: config.max_concurrent_jobs.max(1),
+            throttle: Arc::new(Throttle::new()),
+            registry: JobRegistry::default(),
+            limiter: RateLimiter::new(config.requests_per_minute, Duration::from_secs(60)),
+            tools: ToolCache::default(),
+        }
+    }
+
+    fn active_jobs(&self) -> usize {
+        self.max_jobs - self.jobs.available_permits()
+    }
+}
+
+/// Resolve a per-request output directory inside the clips root.
+///
+/// Only relative paths without `..` are accepted so jobs can't write outside the
+/// directory the server serves.
+fn resolve_output_dir(root: &Path, requested: Option<&str>) -> Result<PathBuf, String> {
+    let Some(requested) = requested.filter(|dir| !dir.trim().is_empty()) else {
+        return Ok(root.to_path_buf());
+    };
+
+    let relative = Path::new(requested);
+    let is_contained = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_contained {
+        return Err(format!(
+            "output_dir must be a relative path inside the clips directory, got {:?}",
+            requested
+        ));
+    }
+
+    Ok(root.join(relative))
+}
+
+/// Whether an `Authorization` header value carries the expected bearer token
+fn bearer_matches(header_value: Option<&str>, expected: &str) -> bool {
+    let Some(token) = header_value.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    // Compare without short-circuiting so the response time doesn't leak the prefix length
+    token.len() == expected.len()
+        && token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn require_auth(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let Some(expected) = state.api_token.as_deref() else {
+        return next.run(request).await;
+    };
+
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if bearer_matches(provided, expected) {
+        return next.run(request).await;
+    }
+
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        Json(ErrorResponse {
+            error: "Missing or invalid API token".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+async fn rate_limit(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match state.limiter.check(addr.ip(), Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+            Json(ErrorResponse {
+                error: "Too many requests, try again later".to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
 pub struct ProcessRequest {
     url: String,
+    /// Id to cancel the job by (`DELETE /api/jobs/:id`); generated (`auto-N`) when omitted
     #[serde(default)]
+    job_id: Option<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<CropMode>")]
     crop_mode: Option<String>,
     #[serde(default)]
     subtitle: Option<bool>,
     #[serde(default)]
+    #[schemars(with = "Option<WhisperModel>")]
     whisper_model: Option<String>,
     #[serde(default)]
     language: Option<String>,
     #[serde(default)]
+    #[schemars(with = "Option<SubtitlePreset>")]
+    subtitle_style: Option<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<SubtitlePosition>")]
+    subtitle_position: Option<String>,
+    #[serde(default)]
     output_dir: Option<String>,
     #[serde(default)]
     gpu: Option<bool>,
+    #[serde(default)]
+    concat_reel: Option<bool>,
+    /// Add a chapter per clip to the reel
+    #[serde(default)]
+    reel_chapters: Option<bool>,
+    /// Also save each clip uncropped (`clip_N_wide.mp4`)
+    #[serde(default)]
+    keep_horizontal: Option<bool>,
+    /// Also make a looping preview of each clip: "gif" or "webp"
+    #[serde(default)]
+    #[schemars(with = "Option<PreviewFormat>")]
+    preview: Option<String>,
+    /// Upscale sources below 720p before cropping (sharper, slower)
+    #[serde(default)]
+    upscale: Option<bool>,
+    /// Video container: "mp4", "mov" or "mkv"
+    #[serde(default)]
+    #[schemars(with = "Option<VideoFormat>")]
+    format: Option<String>,
+    #[serde(default)]
+    crf: Option<u8>,
+    /// Seconds of context around each segment
+    #[serde(default)]
+    padding: Option<f64>,
+    /// Clips to make at most (`MAX_CLIPS` when unset)
+    #[serde(default)]
+    max_clips: Option<usize>,
+    /// Output frame size; both dimensions must be given together
+    #[serde(default)]
+    output_width: Option<u32>,
+    #[serde(default)]
+    output_height: Option<u32>,
+}
+
+/// Longest padding a request may ask for, in seconds
+const MAX_REQUEST_PADDING: f64 = 60.0;
+
+/// Encoding fields of a `ProcessRequest`, validated
+#[derive(Debug, PartialEq)]
+struct EncodingSettings {
+    format: VideoFormat,
+    crf: u8,
+    padding: f64,
+    max_clips: Option<usize>,
+    output_spec: Option<OutputSpec>,
+    preview: Option<PreviewFormat>,
+}
+
+/// Check the optional encoding fields, describing the first invalid one
+fn encoding_settings(payload: &ProcessRequest) -> Result<EncodingSettings, String> {
+    let format = match payload.format.as_deref() {
+        Some(format) => VideoFormat::from_input(format)
+            .ok_or_else(|| format!("Unsupported format '{}' (expected mp4, mov or mkv)", format))?,
+        None => VideoFormat::default(),
+    };
+
+    let crf = payload.crf.unwrap_or(DEFAULT_CRF);
+    if crf > MAX_CRF {
+        return Err(format!("crf must be between 0 and {}", MAX_CRF));
+    }
+
+    let padding = payload.padding.unwrap_or(PADDING);
+    if !(0.0..=MAX_REQUEST_PADDING).contains(&padding) {
+        return Err(format!("padding must be between 0 and {} seconds", MAX_REQUEST_PADDING));
+    }
+
+    if payload.max_clips == Some(0) {
+        return Err("max_clips must be at least 1".to_string());
+    }
+
+    let output_spec = match (payload.output_width, payload.output_height) {
+        (None, None) => None,
+        (Some(width), Some(height)) => Some(
+            OutputSpec::parse(&format!("{}x{}", width, height))
+                .ok_or("output_width and output_height must be positive even numbers")?,
+        ),
+        _ => return Err("output_width and output_height must be given together".to_string()),
+    };
+
+    let preview = match payload.preview.as_deref() {
+        Some(preview) => Some(
+            PreviewFormat::from_input(preview)
+                .ok_or_else(|| format!("Unsupported preview '{}' (expected gif or webp)", preview))?,
+        ),
+        None => None,
+    };
+
+    Ok(EncodingSettings {
+        format,
+        crf,
+        padding,
+        // Unlimited runs are CLI-only: every request gets a bounded amount of work
+        max_clips: payload.max_clips.or(Some(MAX_CLIPS)),
+        output_spec,
+        preview,
+    })
+}
+
+/// Subtitle fields of a `ProcessRequest`, validated
+#[derive(Debug, PartialEq)]
+struct SubtitleSettings {
+    preset: SubtitlePreset,
+    position: Option<SubtitlePosition>,
+    language: String,
 }
 
-#[derive(Serialize)]
+/// Check the optional subtitle fields, describing the first invalid one
+fn subtitle_settings(payload: &ProcessRequest) -> Result<SubtitleSettings, String> {
+    let preset = match payload.subtitle_style.as_deref() {
+        Some(style) => SubtitlePreset::from_input(style).ok_or_else(|| {
+            format!("Unsupported subtitle_style '{}' (expected tiktok, minimal, boxed or karaoke)", style)
+        })?,
+        None => SubtitlePreset::default(),
+    };
+
+    let position = match payload.subtitle_position.as_deref() {
+        Some(position) => Some(SubtitlePosition::from_input(position).ok_or_else(|| {
+            format!("Unsupported subtitle_position '{}' (expected top, center or bottom)", position)
+        })?),
+        None => None,
+    };
+
+    let language = match payload.language.as_deref() {
+        Some(language) => parse_language(language)
+            .ok_or_else(|| format!("Unsupported language '{}' (expected a code like en or id, or auto)", language))?,
+        None => "id".to_string(),
+    };
+
+    Ok(SubtitleSettings {
+        preset,
+        position,
+        language,
+    })
+}
+
+#[derive(Serialize, JsonSchema)]
 struct ProcessResponse {
+    job_id: String,
     message: String,
     files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reel: Option<String>,
+    /// Looping previews when requested, one per entry of `files` (`null` where the
+    /// preview failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previews: Option<Vec<Option<String>>>,
     options: ProcessOptionsResponse,
+    /// Seconds each clip spent per stage, in the order of `files`
+    timings: Vec<StageTimings>,
+    /// Stage totals over all clips
+    total_timings: StageTimings,
+    /// Soft failures, e.g. clips saved without subtitles
+    warnings: Vec<Warning>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct ProcessOptionsResponse {
     crop_mode: String,
     subtitle_enabled: bool,
     whisper_model: Option<String>,
     language: Option<String>,
+    subtitle_style: Option<String>,
     gpu: bool,
+    format: String,
+    crf: u8,
+    padding: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_clips: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_height: Option<u32>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct ErrorResponse {
     error: String,
 }
 
-async fn process_handler(Json(payload): Json<ProcessRequest>) -> impl IntoResponse {
+async fn process_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ProcessRequest>,
+) -> impl IntoResponse {
+    // `full_process` also clips files on disk; clients may only name YouTube videos
+    if extract_video_id(&payload.url).is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid YouTube URL".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
     // Parse crop mode
     let crop_mode = payload
         .crop_mode
@@ -69,26 +745,112 @@ async fn process_handler(Json(payload): Json<ProcessRequest>) -> impl IntoRespon
     // Check subtitle availability
     let subtitle_enabled = payload.subtitle.unwrap_or(false) && check_python_available();
 
-    // Language
-    let language = payload.language.clone().unwrap_or_else(|| "id".to_string());
+    // Subtitle style, position and language
+    let subtitles = match subtitle_settings(&payload) {
+        Ok(subtitles) => subtitles,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+        }
+    };
+    let (subtitle_preset, language) = (subtitles.preset, subtitles.language);
 
-    // Output directory
-    let output_dir = payload.output_dir.clone().unwrap_or_else(|| "clips".to_string());
+    // Output directory (always inside the served clips root)
+    let output_dir = match resolve_output_dir(&state.clips_root, payload.output_dir.as_deref()) {
+        Ok(dir) => dir.to_string_lossy().to_string(),
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+        }
+    };
+
+    // Container, quality and clip selection
+    let encoding = match encoding_settings(&payload) {
+        Ok(encoding) => encoding,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+        }
+    };
 
     // GPU acceleration
     let use_gpu = payload.gpu.unwrap_or(false);
 
     // Build options
-    let subtitle_config = SubtitleConfig::new(subtitle_enabled, whisper_model, &language);
+    let subtitle_config = SubtitleConfig::new(subtitle_enabled, whisper_model, &language)
+        .with_preset(subtitle_preset)
+        .with_position(subtitles.position);
     let options = ProcessOptions::new(crop_mode, subtitle_config, &output_dir)
-        .with_gpu(use_gpu);
+        .with_gpu(use_gpu)
+        .with_concat_reel(payload.concat_reel.unwrap_or(false))
+        .with_reel_chapters(payload.reel_chapters.unwrap_or(false))
+        .with_keep_horizontal(payload.keep_horizontal.unwrap_or(false))
+        .with_preview(encoding.preview)
+        .with_upscale(payload.upscale.unwrap_or(false))
+        .with_proxy(state.proxy.clone())
+        .with_video_format(encoding.format)
+        .with_crf(encoding.crf)
+        .with_padding(encoding.padding)
+        .with_max_clips(encoding.max_clips)
+        .with_output_spec(encoding.output_spec)
+        .with_ffmpeg_path(state.ffmpeg_path.clone())
+        .with_ytdlp_path(state.ytdlp_path.clone());
 
-    // Process video
-    match full_process(&payload.url, &options).await {
-        Ok(files) => {
+    let (job_id, runner, temp_dir) = match state.registry.register(payload.job_id.as_deref(), &payload.url) {
+        Ok(job) => job,
+        Err(error) => {
+            let status = error.status();
+            return (status, Json(ErrorResponse { error: error.to_string() })).into_response();
+        }
+    };
+    let options = options
+        .with_runner(runner)
+        .with_temp_dir(temp_dir)
+        .with_throttle(state.throttle.clone())
+        .with_progress(state.registry.progress_sink(&job_id));
+
+    // Wait for a free job slot and process in a task that `DELETE /api/jobs/:id` can abort
+    let task = tokio::spawn({
+        let (state, job_id, url) = (state.clone(), job_id.clone(), payload.url.clone());
+        async move {
+            // Fewer jobs run at once while YouTube is throttling
+            loop {
+                let allowed = state.throttle.concurrency(state.max_jobs);
+                if allowed == state.max_jobs || state.active_jobs() < allowed {
+                    break;
+                }
+                tokio::time::sleep(THROTTLE_POLL_INTERVAL).await;
+            }
+            let _permit = state.jobs.acquire().await.expect("job semaphore is never closed");
+            if !state.registry.start(&job_id) {
+                return Err(Cancelled.into());
+            }
+            let outcome = full_process(&url, &options).await;
+            let status = if outcome.is_ok() { JobStatus::Done } else { JobStatus::Failed };
+            state.registry.finish(&job_id, status);
+            outcome
+        }
+    });
+    state.registry.set_task(&job_id, task.abort_handle());
+
+    let outcome = match task.await {
+        Ok(outcome) => outcome,
+        Err(e) if e.is_cancelled() => Err(Cancelled.into()),
+        Err(e) => {
+            state.registry.finish(&job_id, JobStatus::Failed);
+            Err(anyhow::anyhow!("Job {} crashed: {}", job_id, e))
+        }
+    };
+    match outcome {
+        Ok(result) => {
             let response = ProcessResponse {
+                job_id,
                 message: "Processing complete".to_string(),
-                files,
+                files: result.files,
+                reel: result.reel,
+                previews: encoding
+                    .preview
+                    .map(|_| result.clips.iter().map(|clip| clip.preview_file.clone()).collect()),
+                total_timings: StageTimings::sum(&result.timings),
+                timings: result.timings,
+                warnings: result.warnings,
                 options: ProcessOptionsResponse {
                     crop_mode: crop_mode.to_string(),
                     subtitle_enabled,
@@ -102,56 +864,672 @@ async fn process_handler(Json(payload): Json<ProcessRequest>) -> impl IntoRespon
                     } else {
                         None
                     },
+                    subtitle_style: if subtitle_enabled {
+                        Some(subtitle_preset.to_string())
+                    } else {
+                        None
+                    },
                     gpu: use_gpu,
+                    format: encoding.format.to_string(),
+                    crf: encoding.crf,
+                    padding: encoding.padding,
+                    max_clips: encoding.max_clips,
+                    output_width: encoding.output_spec.map(|spec| spec.width),
+                    output_height: encoding.output_spec.map(|spec| spec.height),
                 },
             };
             (StatusCode::OK, Json(response)).into_response()
         }
+        Err(e) if e.is::<Cancelled>() => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: format!("Job {} was cancelled", job_id),
+            }),
+        )
+            .into_response(),
         Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            // The video itself can't be clipped (private, removed, ...), not a server fault
+            if e.is::<ClipperError>() {
+                StatusCode::UNPROCESSABLE_ENTITY
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Jobs this server knows about, oldest first
+async fn list_jobs_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(serde_json::json!({ "jobs": state.registry.list() }))
+}
+
+/// Progress of a queued or running job as server-sent events, one `ProgressEvent` as JSON
+/// per message. The stream ends when the job stops.
+async fn job_events_handler(
+    State(state): State<Arc<AppState>>,
+    UrlPath(id): UrlPath<String>,
+) -> Response {
+    let receiver = match state.registry.subscribe(&id) {
+        Some(Some(receiver)) => receiver,
+        Some(None) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse {
+                    error: format!("Job {} already finished", id),
+                }),
+            )
+                .into_response();
+        }
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: format!("No job with id {}", id),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    let events = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((Event::default().json_data(event), receiver)),
+                // A slow client misses events rather than holding the job up
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// Cancel a queued or running job, killing its ffmpeg/yt-dlp process
+async fn cancel_job_handler(
+    State(state): State<Arc<AppState>>,
+    UrlPath(id): UrlPath<String>,
+) -> impl IntoResponse {
+    match state.registry.cancel(&id) {
+        Ok(()) => Json(JobSummary {
+            url: state.registry.with_job(&id, |job| job.url.clone()).unwrap_or_default(),
+            id,
+            status: JobStatus::Cancelled,
+        })
+        .into_response(),
+        Err(CancelError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("No job with id {}", id),
+            }),
+        )
+            .into_response(),
+        Err(CancelError::Finished(status)) => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: format!("Job {} already finished ({})", id, serde_json::to_value(status).unwrap_or_default()),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HeatmapQuery {
+    url: String,
+}
+
+/// One heatmap segment, ranked by score (1 = most replayed)
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+struct HeatmapEntry {
+    rank: usize,
+    start: f64,
+    duration: f64,
+    score: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct HeatmapResponse {
+    video_id: String,
+    segments: Vec<HeatmapEntry>,
+}
+
+fn rank_segments(segments: &[HeatmapSegment]) -> Vec<HeatmapEntry> {
+    segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| HeatmapEntry {
+            rank: i + 1,
+            start: segment.start,
+            duration: segment.duration,
+            score: segment.score,
+        })
+        .collect()
+}
+
+/// Heatmap segments for a video, without downloading or processing anything
+async fn heatmap_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HeatmapQuery>,
+) -> impl IntoResponse {
+    let Some(video_id) = extract_video_id(&query.url) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid YouTube URL".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let options = ProcessOptions::new(
+        CropMode::default(),
+        SubtitleConfig::default(),
+        &state.clips_root.to_string_lossy(),
+    )
+    .with_ytdlp_path(state.ytdlp_path.clone())
+    .with_proxy(state.proxy.clone());
+
+    match fetch_heatmap(&video_id, &options).await {
+        Ok(segments) if !segments.is_empty() => Json(HeatmapResponse {
+            video_id,
+            segments: rank_segments(&segments),
+        })
+        .into_response(),
+        Err(e) if !e.is::<NoHeatmap>() => (
+            StatusCode::BAD_GATEWAY,
             Json(ErrorResponse {
                 error: e.to_string(),
             }),
         )
             .into_response(),
+        // No markers at all, or none scoring high enough
+        _ => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "No high-engagement segments found".to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ValidateRequest {
+    url: String,
+}
+
+/// What a client can expect before submitting a job for a URL
+#[derive(Debug, Default, PartialEq, Serialize, JsonSchema)]
+struct ValidateResponse {
+    /// The URL points at a video that exists (possibly behind a sign-in)
+    valid: bool,
+    video_id: Option<String>,
+    /// Length in seconds, when yt-dlp could read it
+    duration: Option<u64>,
+    /// The watch page has "Most replayed" data, so clips can be picked automatically
+    has_heatmap: bool,
+    /// yt-dlp needs a signed-in session (age-restricted, members-only or private)
+    requires_auth: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Whether `error` means the video exists but needs a signed-in session to download
+fn requires_auth(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ClipperError>(),
+        Some(ClipperError::AgeRestricted | ClipperError::MembersOnly | ClipperError::PrivateVideo)
+    )
+}
+
+/// Check a URL before submitting it: duration via yt-dlp and heatmap presence, in parallel
+async fn validate_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ValidateRequest>,
+) -> Json<ValidateResponse> {
+    let Some(video_id) = extract_video_id(&payload.url) else {
+        return Json(ValidateResponse {
+            error: Some("Invalid YouTube URL".to_string()),
+            ..Default::default()
+        });
+    };
+
+    let options = ProcessOptions::new(
+        CropMode::default(),
+        SubtitleConfig::default(),
+        &state.clips_root.to_string_lossy(),
+    )
+    .with_ytdlp_path(state.ytdlp_path.clone())
+    .with_proxy(state.proxy.clone());
+
+    let duration = {
+        let (video_id, options) = (video_id.clone(), options.clone());
+        let deadline = Instant::now() + VALIDATE_TIMEOUT;
+        tokio::task::spawn_blocking(move || get_duration_until(&video_id, &options, Some(deadline)))
+    };
+    let (duration, heatmap) = tokio::join!(duration, fetch_heatmap(&video_id, &options));
+    let duration = duration.unwrap_or_else(|e| Err(e.into()));
+
+    let mut response = ValidateResponse {
+        video_id: Some(video_id),
+        has_heatmap: heatmap.is_ok_and(|segments| !segments.is_empty()),
+        ..Default::default()
+    };
+    match duration {
+        Ok(duration) => {
+            response.valid = true;
+            response.duration = Some(duration);
+        }
+        Err(e) => {
+            response.requires_auth = requires_auth(&e);
+            response.valid = response.requires_auth;
+            response.error = Some(e.to_string());
+        }
     }
+    Json(response)
+}
+
+/// JSON schemas of the request and response bodies, generated from the types above
+fn api_schema() -> serde_json::Value {
+    serde_json::json!({
+        "POST /api/process": {
+            "request": schema_for!(ProcessRequest),
+            "response": schema_for!(ProcessResponse),
+        },
+        "GET /api/jobs": {
+            "response": schema_for!(Vec<JobSummary>),
+        },
+        "GET /api/jobs/:id/events": {
+            "event": schema_for!(ProgressEvent),
+        },
+        "GET /api/heatmap": {
+            "response": schema_for!(HeatmapResponse),
+        },
+        "POST /api/validate": {
+            "request": schema_for!(ValidateRequest),
+            "response": schema_for!(ValidateResponse),
+        },
+        "error": schema_for!(ErrorResponse),
+    })
+}
+
+async fn schema_handler() -> impl IntoResponse {
+    Json(api_schema())
 }
 
-async fn health_handler() -> impl IntoResponse {
+async fn health_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let tools = match state.tools.get(Instant::now()) {
+        Some(tools) => tools,
+        None => {
+            let (ffmpeg_path, ytdlp_path) = (state.ffmpeg_path.clone(), state.ytdlp_path.clone());
+            let tools = tokio::task::spawn_blocking(move || ToolStatus {
+                versions: detect_versions(ffmpeg_path.as_deref(), ytdlp_path.as_deref()),
+                python: check_python_available(),
+            })
+            .await
+            .unwrap_or_default();
+            state.tools.store(Instant::now(), tools.clone());
+            tools
+        }
+    };
+
     Json(serde_json::json!({
         "status": "ok",
         "version": env!("CARGO_PKG_VERSION"),
+        "dependencies": tools.versions,
+        "jobs": {
+            "active": state.active_jobs(),
+            "queued": state.registry.count(JobStatus::Queued),
+            "max_concurrent": state.max_jobs
+        },
         "features": {
-            "crop_modes": ["default", "split-left", "split-right"],
-            "subtitle": check_python_available(),
-            "whisper_models": ["tiny", "base", "small", "medium", "large"],
-            "gpu": true
+            "crop_modes": CropMode::all().iter().map(CropMode::info).collect::<Vec<_>>(),
+            "subtitle": tools.python,
+            "whisper_models": WhisperModel::all().iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "subtitle_styles": ["tiktok-yellow", "minimal", "boxed", "karaoke"],
+            "gpu": tools.versions.nvenc
         }
     }))
 }
 
-pub async fn start_server(port: u16) {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+pub async fn start_server(port: u16, config: ServerConfig) {
+    let state = Arc::new(AppState::new(&config));
 
-    let app = Router::new()
+    if state.api_token.is_none() {
+        tracing::warn!(
+            "{} is not set: job endpoints are open to anyone who can reach this server",
+            API_TOKEN_ENV
+        );
+    }
+
+    // Job endpoints (anything that downloads, processes or deletes) require the token
+    let protected = Router::new()
         .route("/api/process", post(process_handler))
-        .route("/api/health", axum::routing::get(health_handler))
-        .nest_service("/clips", ServeDir::new("clips"))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth));
+
+    // Managing existing jobs starts no new work, so it needs the token but isn't rate limited
+    let jobs = Router::new()
+        .route("/api/jobs", get(list_jobs_handler))
+        .route("/api/jobs/:id", delete(cancel_job_handler))
+        .route("/api/jobs/:id/events", get(job_events_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth));
+
+    // Discovery is open so clients can check a URL before asking for a token. It is rate
+    // limited, and validating runs yt-dlp only for up to `VALIDATE_TIMEOUT`, which bounds
+    // what an anonymous client can make the server do
+    let discovery = Router::new()
+        .route("/api/heatmap", get(heatmap_handler))
+        .route("/api/validate", post(validate_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit));
+
+    let app = Router::new()
+        .merge(protected)
+        .merge(jobs)
+        .merge(discovery)
+        .route("/api/health", get(health_handler))
+        .route("/api/schema", get(schema_handler))
+        .nest_service("/clips", ServeDir::new(&config.clips_root))
+        .with_state(state.clone())
         .layer(TraceLayer::new_for_http())
         .layer(CorsLayer::permissive());
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     println!("Server running on http://{}", addr);
+    println!(
+        "Max concurrent jobs: {}, rate limit: {} requests/min per IP",
+        config.max_concurrent_jobs, config.requests_per_minute
+    );
     println!("\nAvailable endpoints:");
-    println!("  POST /api/process - Process YouTube video");
+    println!(
+        "  POST /api/process - Process YouTube video{}",
+        if state.api_token.is_some() { " (requires Authorization: Bearer <token>)" } else { "" }
+    );
+    println!("  GET  /api/jobs    - Queued, running and recent jobs");
+    println!("  DELETE /api/jobs/<id> - Cancel a job, killing its ffmpeg/yt-dlp process");
+    println!("  GET  /api/heatmap?url=<url> - Ranked heatmap segments, without processing");
+    println!("  POST /api/validate - Check a URL: duration, heatmap, sign-in required");
     println!("  GET  /api/health  - Health check");
-    println!("  GET  /clips/*     - Serve generated clips");
+    println!("  GET  /api/schema  - JSON schema of request and response bodies");
+    println!(
+        "  GET  /clips/*     - Serve generated clips from {}",
+        config.clips_root.display()
+    );
     println!("\nExample request:");
     println!(r#"  curl -X POST http://localhost:{}/api/process \"#, port);
     println!(r#"    -H "Content-Type: application/json" \"#);
     println!(r#"    -d '{{"url": "https://youtube.com/watch?v=VIDEO_ID", "crop_mode": "default", "subtitle": false, "gpu": true}}'"#);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yt_clipper_rust::CommandRunner;
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let ip: IpAddr = [127, 0, 0, 1].into();
+        let other: IpAddr = [10, 0, 0, 1].into();
+        let now = Instant::now();
+
+        assert!(limiter.check(ip, now).is_ok());
+        assert!(limiter.check(ip, now).is_ok());
+        let retry = limiter.check(ip, now + Duration::from_secs(20)).unwrap_err();
+        assert_eq!(retry, Duration::from_secs(40));
+        assert!(limiter.check(other, now).is_ok());
+
+        // A new window starts once the old one expires
+        assert!(limiter.check(ip, now + Duration::from_secs(61)).is_ok());
+    }
+
+    #[test]
+    fn test_tool_cache_expires() {
+        let cache = ToolCache::default();
+        let now = Instant::now();
+        assert!(cache.get(now).is_none());
+
+        cache.store(now, ToolStatus { python: true, ..ToolStatus::default() });
+        assert!(cache.get(now + Duration::from_secs(60)).unwrap().python);
+        assert!(cache.get(now + TOOLS_TTL).is_none());
+    }
+
+    #[test]
+    fn test_resolve_output_dir() {
+        let root = Path::new("clips");
+        assert_eq!(resolve_output_dir(root, None).unwrap(), PathBuf::from("clips"));
+        assert_eq!(
+            resolve_output_dir(root, Some("results")).unwrap(),
+            PathBuf::from("clips/results")
+        );
+        assert_eq!(
+            resolve_output_dir(root, Some("./a/b")).unwrap(),
+            PathBuf::from("clips/./a/b")
+        );
+        assert!(resolve_output_dir(root, Some("../etc")).is_err());
+        assert!(resolve_output_dir(root, Some("a/../../b")).is_err());
+        assert!(resolve_output_dir(root, Some("/tmp/clips")).is_err());
+    }
+
+    #[test]
+    fn test_encoding_settings() {
+        let request = |body: &str| -> ProcessRequest { serde_json::from_str(body).unwrap() };
+
+        let defaults = encoding_settings(&request(r#"{"url": "u"}"#)).unwrap();
+        assert_eq!(defaults.format, VideoFormat::Mp4);
+        assert_eq!(defaults.crf, DEFAULT_CRF);
+        assert_eq!(defaults.padding, PADDING);
+        assert_eq!(defaults.output_spec, None);
+        assert_eq!(defaults.max_clips, Some(MAX_CLIPS));
+        assert_eq!(defaults.preview, None);
+
+        let custom = encoding_settings(&request(
+            r#"{"url": "u", "format": "mkv", "crf": 18, "padding": 0, "max_clips": 3,
+                "output_width": 1080, "output_height": 1920, "preview": "webp"}"#,
+        ))
+        .unwrap();
+        assert_eq!(custom.format, VideoFormat::Mkv);
+        assert_eq!(custom.crf, 18);
+        assert_eq!(custom.padding, 0.0);
+        assert_eq!(custom.max_clips, Some(3));
+        assert_eq!(custom.output_spec, Some(OutputSpec::new(1080, 1920)));
+        assert_eq!(custom.preview, Some(PreviewFormat::WebP));
+
+        for body in [
+            r#"{"url": "u", "format": "avi"}"#,
+            r#"{"url": "u", "crf": 52}"#,
+            r#"{"url": "u", "padding": -1}"#,
+            r#"{"url": "u", "max_clips": 0}"#,
+            r#"{"url": "u", "output_width": 720}"#,
+            r#"{"url": "u", "output_width": 721, "output_height": 1280}"#,
+            r#"{"url": "u", "preview": "mp4"}"#,
+        ] {
+            assert!(encoding_settings(&request(body)).is_err(), "{}", body);
+        }
+    }
+
+    #[test]
+    fn test_subtitle_settings() {
+        let request = |body: &str| -> ProcessRequest { serde_json::from_str(body).unwrap() };
+
+        let defaults = subtitle_settings(&request(r#"{"url": "u"}"#)).unwrap();
+        assert_eq!(defaults.preset, SubtitlePreset::default());
+        assert_eq!(defaults.position, None);
+        assert_eq!(defaults.language, "id");
+
+        let custom = subtitle_settings(&request(
+            r#"{"url": "u", "subtitle_style": "boxed", "subtitle_position": "top", "language": "EN"}"#,
+        ))
+        .unwrap();
+        assert_eq!(custom.preset, SubtitlePreset::Boxed);
+        assert_eq!(custom.position, Some(SubtitlePosition::Top));
+        assert_eq!(custom.language, "en");
+
+        for body in [
+            r#"{"url": "u", "subtitle_style": "neon"}"#,
+            r#"{"url": "u", "subtitle_position": "left"}"#,
+            r#"{"url": "u", "language": "en_US"}"#,
+        ] {
+            assert!(subtitle_settings(&request(body)).is_err(), "{}", body);
+        }
+    }
+
+    #[test]
+    fn test_rank_segments() {
+        let segments = vec![
+            HeatmapSegment {
+                start: 120.0,
+                duration: 5.0,
+                score: 0.9,
+            },
+            HeatmapSegment {
+                start: 30.0,
+                duration: 5.0,
+                score: 0.6,
+            },
+        ];
+        let ranked = rank_segments(&segments);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(
+            ranked[0],
+            HeatmapEntry {
+                rank: 1,
+                start: 120.0,
+                duration: 5.0,
+                score: 0.9
+            }
+        );
+        assert_eq!(ranked[1].rank, 2);
+    }
+
+    #[tokio::test]
+    async fn test_heatmap_rejects_invalid_url() {
+        let state = Arc::new(AppState::new(&ServerConfig::default()));
+        let query = HeatmapQuery {
+            url: "https://example.com/not-youtube".to_string(),
+        };
+        let response = heatmap_handler(State(state), Query(query)).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_validate_rejects_invalid_url() {
+        let state = Arc::new(AppState::new(&ServerConfig::default()));
+        let payload = ValidateRequest {
+            url: "https://example.com/not-youtube".to_string(),
+        };
+        let Json(response) = validate_handler(State(state), Json(payload)).await;
+        assert!(!response.valid);
+        assert_eq!(response.video_id, None);
+        assert_eq!(response.error.as_deref(), Some("Invalid YouTube URL"));
+
+        assert!(requires_auth(&ClipperError::AgeRestricted.into()));
+        assert!(!requires_auth(&ClipperError::VideoUnavailable.into()));
+    }
+
+    #[test]
+    fn test_api_schema_lists_enums() {
+        let schema = api_schema();
+        let request = &schema["POST /api/process"]["request"];
+        assert_eq!(request["required"], serde_json::json!(["url"]));
+        let defs = &request["$defs"];
+        assert!(defs["CropMode"]["enum"].as_array().unwrap().contains(&"split-left".into()));
+        assert_eq!(defs["WhisperModel"]["enum"].as_array().unwrap().len(), WhisperModel::all().len());
+        assert_eq!(defs["VideoFormat"]["enum"], serde_json::json!(["mp4", "mov", "mkv"]));
+
+        let response = &schema["POST /api/process"]["response"];
+        // Documented variants become `oneOf` entries with a description each
+        assert!(response["$defs"]["WarningKind"].to_string().contains(r#""const":"clip-failed""#));
+    }
+
+    #[test]
+    fn test_job_registry_cancel() {
+        let registry = JobRegistry::default();
+        let (id, runner, temp_dir) = registry.register(None, "https://youtu.be/abc123DEF45").unwrap();
+        assert_eq!(id, "auto-1");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("temp_abc123DEF45_1.mp4"), b"partial").unwrap();
+
+        // Ids of jobs in progress can't be reused, and must be safe in a path
+        let invalid = |id| registry.register(Some(id), "u").unwrap_err().status() == StatusCode::BAD_REQUEST;
+        assert_eq!(
+            registry.register(Some("auto-1"), "u").unwrap_err(),
+            RegisterError::InvalidId("job_id can't start with 'auto-'".to_string())
+        );
+        assert!(invalid("auto-7"));
+        assert!(registry.register(Some("1"), "u").is_ok());
+        assert!(invalid("../x"));
+        let in_progress = registry.register(Some("1"), "u").unwrap_err();
+        assert_eq!(in_progress.status(), StatusCode::CONFLICT);
+        assert_eq!(in_progress.to_string(), "Job 1 is already in progress");
+
+        assert!(registry.start(&id));
+        assert_eq!(registry.status(&id), Some(JobStatus::Running));
+        let task = tokio::runtime::Runtime::new().unwrap().spawn(std::future::pending::<()>());
+        registry.set_task(&id, task.abort_handle());
+        registry.cancel(&id).unwrap();
+        assert_eq!(registry.status(&id), Some(JobStatus::Cancelled));
+        assert!(runner.is_cancelled());
+        // The running job may still be writing: its files stay until its task winds down,
+        // and so does its id
+        assert!(temp_dir.exists());
+        assert!(registry.register(Some(&id), "u").is_err());
+
+        // The task winding down with an error doesn't overwrite the cancellation
+        registry.finish(&id, JobStatus::Failed);
+        assert_eq!(registry.status(&id), Some(JobStatus::Cancelled));
+        assert!(!temp_dir.exists());
+        assert_eq!(registry.cancel(&id), Err(CancelError::Finished(JobStatus::Cancelled)));
+        assert_eq!(registry.cancel("missing"), Err(CancelError::NotFound));
+
+        let (custom, _, _) = registry.register(Some("nightly-run"), "u").unwrap();
+        assert_eq!(registry.count(JobStatus::Queued), 2);
+        // A queued job never started, so it has nothing to wind down
+        let (queued, _, queued_temp_dir) = registry.register(Some("queued"), "u").unwrap();
+        std::fs::create_dir_all(&queued_temp_dir).unwrap();
+        registry.cancel(&queued).unwrap();
+        assert!(!queued_temp_dir.exists());
+        assert!(!registry.start(&queued));
+        registry.finish(&custom, JobStatus::Done);
+        assert_eq!(registry.status(&custom), Some(JobStatus::Done));
+    }
+
+    #[test]
+    fn test_job_registry_progress_events() {
+        let registry = JobRegistry::default();
+        let (id, _, _) = registry.register(Some("progress"), "u").unwrap();
+        let sink = registry.progress_sink(&id).unwrap();
+        let mut receiver = registry.subscribe(&id).unwrap().unwrap();
+
+        sink.emit(ProgressEvent::ClipStarted { index: 1 });
+        assert_eq!(receiver.try_recv().unwrap(), ProgressEvent::ClipStarted { index: 1 });
+
+        // The stream closes once the job stops and its sink is gone
+        registry.finish(&id, JobStatus::Done);
+        drop(sink);
+        assert_eq!(receiver.try_recv(), Err(broadcast::error::TryRecvError::Closed));
+        assert!(registry.subscribe(&id).unwrap().is_none());
+        assert!(registry.subscribe("missing").is_none());
+    }
+
+    #[test]
+    fn test_bearer_matches() {
+        assert!(bearer_matches(Some("Bearer s3cret"), "s3cret"));
+        assert!(!bearer_matches(Some("Bearer s3cre"), "s3cret"));
+        assert!(!bearer_matches(Some("Bearer s3creT"), "s3cret"));
+        assert!(!bearer_matches(Some("s3cret"), "s3cret"));
+        assert!(!bearer_matches(None, "s3cret"));
+    }
 }
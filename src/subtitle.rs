@@ -1,31 +1,81 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use strum::{Display, EnumString};
-
-/// Available Whisper model sizes
-#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+use tracing::info;
+
+use crate::escape::{escape_ass_text, escape_filter_path};
+use crate::crop::CropMode;
+use crate::deps;
+use crate::move_file;
+use crate::profile::{self, StageTimings};
+use crate::runner::{CommandOutput, CommandRunner};
+use crate::warning::{warn, Warning, WarningKind};
+
+pub mod style;
+pub mod transcript;
+
+pub use style::{HighlightAnimation, SubtitleEffect, SubtitlePosition, SubtitlePreset, SubtitleStyle};
+pub use transcript::{transcript_cache_dir, TranscriptCache};
+
+/// Available Whisper models, smallest first.
+///
+/// Names match both whisper.cpp's ggml files and faster-whisper's model ids. Turbo is
+/// large-v3 with a pruned decoder, close to large quality at several times the speed; the
+/// distil models are faster still but only transcribe English.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize, Display, EnumString,
+    schemars::JsonSchema,
+)]
 #[strum(serialize_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum WhisperModel {
     Tiny,
     Base,
+    #[strum(serialize = "distil-small.en")]
+    #[serde(rename = "distil-small.en")]
+    DistilSmallEn,
     #[default]
     Small,
     Medium,
+    #[strum(serialize = "distil-large-v3")]
+    #[serde(rename = "distil-large-v3")]
+    DistilLargeV3,
+    #[strum(serialize = "large-v3-turbo")]
+    #[serde(rename = "large-v3-turbo")]
+    LargeV3Turbo,
     Large,
 }
 
 impl WhisperModel {
+    /// All available models, smallest first
+    pub fn all() -> &'static [WhisperModel] {
+        &[
+            WhisperModel::Tiny,
+            WhisperModel::Base,
+            WhisperModel::DistilSmallEn,
+            WhisperModel::Small,
+            WhisperModel::Medium,
+            WhisperModel::DistilLargeV3,
+            WhisperModel::LargeV3Turbo,
+            WhisperModel::Large,
+        ]
+    }
+
     /// Get approximate model size for display
     pub fn size_display(&self) -> &'static str {
         match self {
             WhisperModel::Tiny => "~75 MB",
             WhisperModel::Base => "~142 MB",
+            WhisperModel::DistilSmallEn => "~336 MB",
             WhisperModel::Small => "~466 MB",
             WhisperModel::Medium => "~1.5 GB",
+            WhisperModel::DistilLargeV3 => "~1.5 GB",
+            WhisperModel::LargeV3Turbo => "~1.6 GB",
             WhisperModel::Large => "~2.9 GB",
         }
     }
@@ -35,18 +85,41 @@ impl WhisperModel {
         match self {
             WhisperModel::Tiny => "ggml-tiny.bin",
             WhisperModel::Base => "ggml-base.bin",
+            WhisperModel::DistilSmallEn => "ggml-distil-small.en.bin",
             WhisperModel::Small => "ggml-small.bin",
             WhisperModel::Medium => "ggml-medium.bin",
+            WhisperModel::DistilLargeV3 => "ggml-distil-large-v3.bin",
+            WhisperModel::LargeV3Turbo => "ggml-large-v3-turbo.bin",
             WhisperModel::Large => "ggml-large.bin",
         }
     }
 
-    /// Get HuggingFace download URL for the model
+    /// Get HuggingFace download URL for the model.
+    ///
+    /// The distil models are published by distil-whisper rather than whisper.cpp.
     pub fn download_url(&self) -> String {
-        format!(
-            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/{}",
-            self.ggml_filename()
-        )
+        let repo = match self {
+            WhisperModel::DistilSmallEn => "distil-whisper/distil-small.en",
+            WhisperModel::DistilLargeV3 => "distil-whisper/distil-large-v3-ggml",
+            _ => "ggerganov/whisper.cpp",
+        };
+        format!("https://huggingface.co/{}/resolve/main/{}", repo, self.ggml_filename())
+    }
+
+    /// Whether the model only transcribes English
+    pub fn is_english_only(&self) -> bool {
+        matches!(self, WhisperModel::DistilSmallEn | WhisperModel::DistilLargeV3)
+    }
+
+    /// Smallest model that transcribes `language` reasonably.
+    ///
+    /// Tiny and base were trained mostly on English; for other languages (and for `auto`,
+    /// whose detection they also get wrong) they produce garbled or English text.
+    pub fn minimum_for_language(language: &str) -> WhisperModel {
+        match language.trim().to_lowercase().as_str() {
+            "en" | "english" => WhisperModel::Tiny,
+            _ => WhisperModel::Small,
+        }
     }
 
     /// Parse from user input
@@ -57,6 +130,9 @@ impl WhisperModel {
             "small" => Some(WhisperModel::Small),
             "medium" => Some(WhisperModel::Medium),
             "large" | "large-v1" | "large-v2" | "large-v3" => Some(WhisperModel::Large),
+            "turbo" | "large-v3-turbo" | "large-turbo" => Some(WhisperModel::LargeV3Turbo),
+            "distil-small" | "distil-small.en" | "distil-small-en" => Some(WhisperModel::DistilSmallEn),
+            "distil-large" | "distil-large-v3" | "distil" => Some(WhisperModel::DistilLargeV3),
             _ => None,
         }
     }
@@ -72,13 +148,172 @@ pub enum SubtitleBackend {
     FasterWhisper,
 }
 
+/// How subtitles are added to the clip
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleMode {
+    /// Render into the video frames (re-encodes the video)
+    #[default]
+    Burn,
+    /// Mux as a toggleable `mov_text` caption track (no re-encode, no styling)
+    Embed,
+}
+
+impl SubtitleMode {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "burn" | "hard" => Some(SubtitleMode::Burn),
+            "embed" | "soft" => Some(SubtitleMode::Embed),
+            _ => None,
+        }
+    }
+}
+
+/// Subtitle file written next to the clip in addition to the burned/embedded subtitles
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    /// The styled ASS file used for burning
+    #[default]
+    Ass,
+    /// WebVTT with one cue per spoken word and `<c>` classes for the highlight, so the
+    /// word timing survives for re-styling (needs whisper.cpp word timestamps)
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().trim_start_matches('.').to_lowercase().as_str() {
+            "ass" => Some(SubtitleFormat::Ass),
+            "vtt" | "webvtt" => Some(SubtitleFormat::Vtt),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Ass => "ass",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+/// What whisper produces from the speech
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WhisperTask {
+    /// Text in the spoken language
+    #[default]
+    Transcribe,
+    /// English translation of the speech (whisper only translates into English)
+    Translate,
+}
+
+/// Language value that lets whisper detect the spoken language
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Parse a language from user input: a language code such as `id`/`en`/`ja`, or
+/// `auto` (also `detect`) for automatic detection
+pub fn parse_language(input: &str) -> Option<String> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "auto" | "detect" => Some(AUTO_LANGUAGE.to_string()),
+        code if !code.is_empty() && code.chars().all(|c| c.is_ascii_alphabetic() || c == '-') => {
+            Some(input)
+        }
+        _ => None,
+    }
+}
+
+/// Extract the language whisper detected from its log output
+/// (e.g. `auto-detected language: en (p = 0.97)`)
+pub fn parse_detected_language(output: &str) -> Option<String> {
+    let marker = "auto-detected language:";
+    let line = output.lines().find(|l| l.contains(marker))?;
+    let rest = &line[line.find(marker)? + marker.len()..];
+    let code: String = rest
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '-')
+        .collect();
+    if code.is_empty() {
+        None
+    } else {
+        Some(code)
+    }
+}
+
+/// Log (and return) the detected language when running in auto mode
+fn log_detected_language(config: &SubtitleConfig, output: &CommandOutput) -> Option<String> {
+    if !config.is_auto_language() {
+        return None;
+    }
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let detected = parse_detected_language(&text);
+    match &detected {
+        Some(language) => info!("  Detected language: {}", language),
+        None => info!("  Language auto-detection did not report a language"),
+    }
+    detected
+}
+
 /// Subtitle configuration
 #[derive(Debug, Clone)]
 pub struct SubtitleConfig {
     pub enabled: bool,
     pub model: WhisperModel,
+    /// Language code passed to whisper, or `auto` to detect it
     pub language: String,
     pub backend: SubtitleBackend,
+    /// Preset the current style was derived from
+    pub preset: SubtitlePreset,
+    /// Style used for generated ASS subtitles
+    pub style: SubtitleStyle,
+    /// Styles replacing `style` for some languages (e.g. a CJK font for `ja`), keyed by
+    /// language code. The configured language picks one, or the detected one in auto mode.
+    pub language_styles: HashMap<String, SubtitleStyle>,
+    /// Only subtitle clips whose heatmap score is at least this
+    pub subtitle_min_score: Option<f64>,
+    /// Burn subtitles into the video or embed them as a caption track
+    pub mode: SubtitleMode,
+    /// Also keep a subtitle file next to each clip
+    pub sidecar: Option<SubtitleFormat>,
+    /// Beam search width used for decoding
+    pub beam_size: u32,
+    /// Sampling temperature; 0.0 decodes greedily/deterministically
+    pub temperature: f32,
+    /// Skip silent stretches with voice activity detection (reduces hallucinated text)
+    pub vad: bool,
+    /// CPU threads whisper.cpp transcribes with. `None` uses every available core
+    /// (whisper.cpp itself defaults to at most 4).
+    pub whisper_threads: Option<usize>,
+    /// Whether cached word-level transcripts are reused (whisper.cpp only)
+    pub transcript_cache: TranscriptCache,
+    /// ffmpeg binary for audio extraction and burning (see `deps::ffmpeg_program`)
+    pub ffmpeg_path: Option<PathBuf>,
+    /// Transcribe, or translate the speech into English
+    pub task: WhisperTask,
+    /// Two stacked lines per phrase: the transcript on top, its English translation below.
+    /// Whisper runs twice, and the lines are shown per phrase without word highlighting.
+    pub dual: bool,
+    /// Position the user asked for; `None` picks one per crop mode (see `for_crop_mode`)
+    pub position: Option<SubtitlePosition>,
+    /// Put the MP4 index at the front when burning or embedding (see
+    /// `ProcessOptions::faststart`)
+    pub faststart: bool,
+    /// Save the generated subtitle file next to the clip instead of adding it, for editing
+    /// before the final render
+    pub subtitle_only: bool,
+    /// Switch to `WhisperModel::minimum_for_language` when the chosen model is too small
+    /// for the language, instead of only warning about it
+    pub auto_upgrade_model: bool,
 }
 
 impl Default for SubtitleConfig {
@@ -88,6 +323,24 @@ impl Default for SubtitleConfig {
             model: WhisperModel::Small,
             language: "id".to_string(),
             backend: SubtitleBackend::WhisperCpp,
+            preset: SubtitlePreset::default(),
+            style: SubtitlePreset::default().to_style(),
+            language_styles: HashMap::new(),
+            subtitle_min_score: None,
+            mode: SubtitleMode::default(),
+            sidecar: None,
+            beam_size: DEFAULT_BEAM_SIZE,
+            whisper_threads: None,
+            temperature: DEFAULT_TEMPERATURE,
+            vad: false,
+            transcript_cache: TranscriptCache::default(),
+            ffmpeg_path: None,
+            task: WhisperTask::default(),
+            dual: false,
+            position: None,
+            subtitle_only: false,
+            faststart: true,
+            auto_upgrade_model: false,
         }
     }
 }
@@ -108,6 +361,7 @@ impl SubtitleConfig {
             model,
             language: language.to_string(),
             backend,
+            ..Default::default()
         }
     }
 
@@ -115,10 +369,179 @@ impl SubtitleConfig {
         self.backend = backend;
         self
     }
+
+    /// Whether whisper should detect the language itself
+    pub fn is_auto_language(&self) -> bool {
+        self.language == AUTO_LANGUAGE
+    }
+
+    /// Apply a style preset, replacing the current style
+    pub fn with_preset(mut self, preset: SubtitlePreset) -> Self {
+        self.preset = preset;
+        self.style = preset.to_style();
+        self
+    }
+
+    pub fn with_mode(mut self, mode: SubtitleMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_min_score(mut self, min_score: Option<f64>) -> Self {
+        self.subtitle_min_score = min_score;
+        self
+    }
+
+    pub fn with_sidecar(mut self, sidecar: Option<SubtitleFormat>) -> Self {
+        self.sidecar = sidecar;
+        self
+    }
+
+    /// Set the decoding parameters passed to whisper
+    pub fn with_decoding(mut self, beam_size: u32, temperature: f32, vad: bool) -> Self {
+        self.beam_size = beam_size.max(1);
+        self.temperature = temperature.max(0.0);
+        self.vad = vad;
+        self
+    }
+
+    pub fn with_whisper_threads(mut self, threads: Option<usize>) -> Self {
+        self.whisper_threads = threads.map(|threads| threads.max(1));
+        self
+    }
+
+    /// Threads to run whisper.cpp with: `whisper_threads`, or the available parallelism
+    pub fn thread_count(&self) -> usize {
+        self.whisper_threads
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(4, |threads| threads.get()))
+    }
+
+    pub fn with_transcript_cache(mut self, cache: TranscriptCache) -> Self {
+        self.transcript_cache = cache;
+        self
+    }
+
+    pub fn with_task(mut self, task: WhisperTask) -> Self {
+        self.task = task;
+        self
+    }
+
+    pub fn with_dual(mut self, dual: bool) -> Self {
+        self.dual = dual;
+        self
+    }
+
+    pub fn with_position(mut self, position: Option<SubtitlePosition>) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_subtitle_only(mut self, subtitle_only: bool) -> Self {
+        self.subtitle_only = subtitle_only;
+        self
+    }
+
+    pub fn with_auto_upgrade_model(mut self, auto_upgrade_model: bool) -> Self {
+        self.auto_upgrade_model = auto_upgrade_model;
+        self
+    }
+
+    /// Use `style` for subtitles in `language` (a whisper language code, e.g. `ja`)
+    pub fn with_language_style(mut self, language: &str, style: SubtitleStyle) -> Self {
+        self.language_styles.insert(language.trim().to_lowercase(), style);
+        self
+    }
+
+    /// Style for subtitles in the configured language, or in `detected` when the language
+    /// is auto-detected; `style` when no language style matches
+    pub fn style_for_language(&self, detected: Option<&str>) -> &SubtitleStyle {
+        let language = if self.is_auto_language() {
+            detected
+        } else {
+            Some(self.language.as_str())
+        };
+        language
+            .and_then(|language| self.language_styles.get(&language.to_lowercase()))
+            .unwrap_or(&self.style)
+    }
+
+    /// The default style and every language style
+    fn styles_mut(&mut self) -> impl Iterator<Item = &mut SubtitleStyle> {
+        std::iter::once(&mut self.style).chain(self.language_styles.values_mut())
+    }
+
+    /// A larger model to use when `model` is known to transcribe `language` poorly
+    pub fn suggested_model(&self) -> Option<WhisperModel> {
+        let minimum = WhisperModel::minimum_for_language(&self.language);
+        if self.model.is_english_only() && minimum != WhisperModel::Tiny {
+            // The multilingual model of about the same speed
+            return Some(match self.model {
+                WhisperModel::DistilLargeV3 => WhisperModel::LargeV3Turbo,
+                _ => minimum,
+            });
+        }
+        (self.model < minimum).then_some(minimum)
+    }
+
+    /// Resolve the style position for clips cropped with `crop_mode`: the explicit
+    /// `position` if set, otherwise `CropMode::subtitle_position`
+    pub fn for_crop_mode(mut self, crop_mode: CropMode) -> Self {
+        let position = self.position.unwrap_or_else(|| crop_mode.subtitle_position());
+        for style in self.styles_mut() {
+            style.position = position;
+        }
+        self
+    }
+
+    /// Subtitle file extension: ASS for whisper.cpp and dual captions, SRT for faster-whisper
+    fn subtitle_extension(&self) -> &'static str {
+        match self.backend {
+            _ if self.dual => "ass",
+            SubtitleBackend::WhisperCpp => "ass",
+            SubtitleBackend::FasterWhisper => "srt",
+        }
+    }
+
+    /// The ffmpeg binary subtitle steps run
+    pub fn ffmpeg(&self) -> PathBuf {
+        deps::ffmpeg_program(self.ffmpeg_path.as_deref())
+    }
+
+    /// Whether transcription should ask whisper for word-level timestamps
+    fn wants_word_timing(&self) -> bool {
+        self.style.needs_word_timing() || self.sidecar == Some(SubtitleFormat::Vtt)
+    }
+
+    /// Whether a clip with the given heatmap score should get subtitles
+    pub fn applies_to_score(&self, score: f64) -> bool {
+        self.enabled && self.subtitle_min_score.is_none_or(|min| score >= min)
+    }
 }
 
+/// Default beam size (whisper.cpp and faster-whisper both use 5)
+pub const DEFAULT_BEAM_SIZE: u32 = 5;
+
+/// Default sampling temperature (both backends start from 0.0)
+pub const DEFAULT_TEMPERATURE: f32 = 0.0;
+
+/// Silero VAD model used by whisper.cpp's `--vad`
+pub const WHISPER_VAD_MODEL: &str = "ggml-silero-v5.1.2.bin";
+
+/// Where `WHISPER_VAD_MODEL` can be downloaded from
+pub const WHISPER_VAD_MODEL_URL: &str =
+    "https://huggingface.co/ggml-org/whisper-vad/resolve/main/ggml-silero-v5.1.2.bin";
+
+/// Environment variable overriding the whisper.cpp models directory
+pub const WHISPER_MODELS_DIR_ENV: &str = "WHISPER_MODELS_DIR";
+
 /// Get the whisper.cpp models directory
 pub fn get_whisper_cpp_models_dir() -> std::path::PathBuf {
+    if let Some(dir) = std::env::var_os(WHISPER_MODELS_DIR_ENV).filter(|d| !d.is_empty()) {
+        let dir = std::path::PathBuf::from(dir);
+        let _ = fs::create_dir_all(&dir);
+        return dir;
+    }
+
     // Check common locations
     if let Some(home) = dirs::home_dir() {
         let whisper_dir = home.join(".cache").join("whisper.cpp");
@@ -134,63 +557,48 @@ pub fn get_whisper_cpp_models_dir() -> std::path::PathBuf {
     std::path::PathBuf::from("models")
 }
 
-/// Check if whisper.cpp binary is available
-pub fn check_whisper_cpp_available() -> bool {
-    // Try common binary names (whisper-cli is from scoop)
-    let binary_names = ["whisper-cli", "whisper", "whisper-cpp", "main"];
-
-    for name in binary_names {
-        if which::which(name).is_ok() {
-            return true;
-        }
-    }
-
-    // Check in current directory
-    let current_dir_binaries = [
-        "whisper-cli.exe",
-        "whisper.exe",
-        "whisper-cpp.exe",
-        "main.exe",
-        "whisper-cli",
-        "whisper",
-        "main",
-    ];
-    for name in current_dir_binaries {
-        if std::path::Path::new(name).exists() {
-            return true;
+/// Binary names whisper.cpp is installed under (whisper-cli is from scoop)
+const WHISPER_CPP_BINARY_NAMES: [&str; 4] = ["whisper-cli", "whisper", "whisper-cpp", "main"];
+
+/// Binaries looked for in the current directory
+const WHISPER_CPP_LOCAL_BINARIES: [&str; 7] = [
+    "whisper-cli.exe",
+    "whisper.exe",
+    "whisper-cpp.exe",
+    "main.exe",
+    "whisper-cli",
+    "whisper",
+    "main",
+];
+
+/// Every whisper.cpp binary found, in order of preference
+fn whisper_cpp_binaries() -> Vec<String> {
+    let on_path = WHISPER_CPP_BINARY_NAMES
+        .iter()
+        .filter(|name| which::which(name).is_ok());
+    let local = WHISPER_CPP_LOCAL_BINARIES
+        .iter()
+        .filter(|name| std::path::Path::new(name).exists())
+        // A bare name would be looked up on PATH, not in the current directory
+        .map(|name| format!("./{}", name));
+
+    let mut binaries: Vec<String> = Vec::new();
+    for name in on_path.map(|name| name.to_string()).chain(local) {
+        if !binaries.contains(&name) {
+            binaries.push(name);
         }
     }
+    binaries
+}
 
-    false
+/// Check if whisper.cpp binary is available
+pub fn check_whisper_cpp_available() -> bool {
+    !whisper_cpp_binaries().is_empty()
 }
 
 /// Get the whisper.cpp binary path
 fn get_whisper_cpp_binary() -> Option<String> {
-    let binary_names = ["whisper-cli", "whisper", "whisper-cpp", "main"];
-
-    for name in binary_names {
-        if which::which(name).is_ok() {
-            return Some(name.to_string());
-        }
-    }
-
-    // Check in current directory
-    let current_dir_binaries = [
-        "whisper-cli.exe",
-        "whisper.exe",
-        "whisper-cpp.exe",
-        "main.exe",
-        "whisper-cli",
-        "whisper",
-        "main",
-    ];
-    for name in current_dir_binaries {
-        if std::path::Path::new(name).exists() {
-            return Some(format!("./{}", name));
-        }
-    }
-
-    None
+    whisper_cpp_binaries().into_iter().next()
 }
 
 /// Check if the whisper.cpp model exists
@@ -208,18 +616,18 @@ pub fn download_whisper_model(model: WhisperModel) -> Result<std::path::PathBuf>
     let model_path = models_dir.join(model.ggml_filename());
 
     if model_path.exists() {
-        println!("  Model already exists: {}", model_path.display());
+        info!("  Model already exists: {}", model_path.display());
         return Ok(model_path);
     }
 
     let url = model.download_url();
-    println!(
+    info!(
         "  Downloading {} model ({})...",
         model,
         model.size_display()
     );
-    println!("  URL: {}", url);
-    println!("  Destination: {}", model_path.display());
+    info!("  URL: {}", url);
+    info!("  Destination: {}", model_path.display());
 
     // Try curl first
     let status = Command::new("curl")
@@ -231,14 +639,14 @@ pub fn download_whisper_model(model: WhisperModel) -> Result<std::path::PathBuf>
 
     match status {
         Ok(s) if s.success() => {
-            println!("  Model downloaded successfully!");
+            info!("  Model downloaded successfully!");
             return Ok(model_path);
         }
         _ => {
             // Try PowerShell on Windows
             #[cfg(target_os = "windows")]
             {
-                println!("  Trying PowerShell download...");
+                info!("  Trying PowerShell download...");
                 let ps_command = format!(
                     "Invoke-WebRequest -Uri '{}' -OutFile '{}'",
                     url,
@@ -250,7 +658,7 @@ pub fn download_whisper_model(model: WhisperModel) -> Result<std::path::PathBuf>
 
                 if let Ok(s) = status {
                     if s.success() {
-                        println!("  Model downloaded successfully!");
+                        info!("  Model downloaded successfully!");
                         return Ok(model_path);
                     }
                 }
@@ -266,17 +674,24 @@ pub fn download_whisper_model(model: WhisperModel) -> Result<std::path::PathBuf>
 }
 
 /// Extract audio from video using FFmpeg (required for whisper.cpp)
-fn extract_audio(video_file: &str, audio_file: &str) -> Result<()> {
-    let status = Command::new("ffmpeg")
-        .args(["-y", "-hide_banner", "-loglevel", "error"])
-        .args(["-i", video_file])
-        .args(["-ar", "16000"]) // 16kHz sample rate required by Whisper
-        .args(["-ac", "1"]) // Mono
-        .args(["-c:a", "pcm_s16le"]) // 16-bit PCM
-        .arg(audio_file)
-        .status()?;
-
-    if status.success() {
+fn extract_audio(
+    video_file: &str,
+    audio_file: &str,
+    ffmpeg: &Path,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    let status = runner.run(
+        Command::new(ffmpeg)
+            .args(["-y", "-hide_banner", "-loglevel", "error"])
+            .args(["-i", video_file])
+            .args(["-ar", "16000"]) // 16kHz sample rate required by Whisper
+            .args(["-ac", "1"]) // Mono
+            .args(["-c:a", "pcm_s16le"]) // 16-bit PCM
+            .arg(audio_file),
+        None,
+    )?;
+
+    if status == Some(true) {
         Ok(())
     } else {
         Err(anyhow!("Failed to extract audio from video"))
@@ -284,11 +699,12 @@ fn extract_audio(video_file: &str, audio_file: &str) -> Result<()> {
 }
 
 /// Word with timestamp from whisper
-#[derive(Debug, Clone)]
-struct TimedWord {
-    text: String,
-    start: f64,
-    end: f64,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimedWord {
+    pub text: String,
+    /// Seconds from the start of the transcribed media
+    pub start: f64,
+    pub end: f64,
 }
 
 /// Parse whisper.cpp JSON output to get word-level timestamps
@@ -401,14 +817,73 @@ fn format_ass_time(seconds: f64) -> String {
     format!("{}:{:02}:{:02}.{:02}", h, m, s, cs)
 }
 
-/// Generate ASS subtitle with word-by-word highlight animation (TikTok/CapCut style)
-fn generate_ass_with_word_highlight(words: &[TimedWord], output_file: &str) -> Result<()> {
+/// Format time for WebVTT (HH:MM:SS.mmm)
+fn format_vtt_time(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        ms % 1000
+    )
+}
+
+/// Escape cue text for WebVTT
+fn escape_vtt_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Generate WebVTT with one cue per word, mirroring the ASS word highlight with
+/// `<c.active>`, `<c.spoken>` and `<c.upcoming>` spans styled from `style`
+fn generate_vtt_with_word_timing(
+    words: &[TimedWord],
+    output_file: &str,
+    style: &SubtitleStyle,
+) -> Result<()> {
     let mut file = fs::File::create(output_file)?;
+    write!(
+        file,
+        "WEBVTT\n\nSTYLE\n::cue(.active) {{ color: {}; }}\n::cue(.spoken) {{ color: {}; }}\n::cue(.upcoming) {{ color: {}; }}\n\n",
+        style.active_color, style.spoken_color, style.upcoming_color
+    )?;
+
+    let words = split_long_words(words, MAX_PHRASE_WIDTH);
+    for phrase_words in group_into_phrases(&words) {
+        for (word_idx, word) in phrase_words.iter().enumerate() {
+            let text = join_words(&phrase_words, |i, w| {
+                let class = match i.cmp(&word_idx) {
+                    std::cmp::Ordering::Less => "spoken",
+                    std::cmp::Ordering::Equal => "active",
+                    std::cmp::Ordering::Greater => "upcoming",
+                };
+                format!("<c.{}>{}</c>", class, escape_vtt_text(&w.text))
+            });
+            write!(
+                file,
+                "{} --> {}\n{}\n\n",
+                format_vtt_time(word.start),
+                format_vtt_time(word.end.max(word.start + 0.1)),
+                text
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the ASS header with a single `Default` style
+fn ass_header(title: &str, style: &SubtitleStyle) -> String {
+    ass_header_with_styles(title, &[style.to_ass_style_line("Default")])
+}
 
-    // ASS Header with styles optimized for word-by-word animation
-    // Using transform effects for pop animation
-    let header = r#"[Script Info]
-Title: Word Highlight Subtitles
+/// ASS header declaring several `Style:` lines (see `SubtitleStyle::to_ass_style_line`)
+fn ass_header_with_styles(title: &str, styles: &[String]) -> String {
+    format!(
+        "[Script Info]
+Title: {}
 ScriptType: v4.00+
 PlayResX: 720
 PlayResY: 1280
@@ -417,53 +892,248 @@ ScaledBorderAndShadow: yes
 
 [V4+ Styles]
 Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
-Style: Default,Arial Black,52,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,1,0,0,0,100,100,0,0,1,4,0,2,20,20,80,1
-Style: Active,Arial Black,58,&H0000FFFF,&H00FFFFFF,&H00000000,&H80000000,1,0,0,0,100,100,0,0,1,4,0,2,20,20,80,1
-Style: Inactive,Arial Black,48,&H80FFFFFF,&H000000FF,&H00000000,&H40000000,1,0,0,0,100,100,0,0,1,3,0,2,20,20,80,1
+{}
 
 [Events]
 Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
-"#;
+",
+        title,
+        styles.join("\n")
+    )
+}
+
+/// Maximum display width of a phrase (wide CJK characters count double)
+const MAX_PHRASE_WIDTH: usize = 20;
+
+/// Maximum words per phrase for space-separated scripts
+const MAX_WORDS_PER_PHRASE: usize = 3;
+
+/// Whether `c` belongs to a script written without spaces between words (Chinese,
+/// Japanese, Thai, ...)
+fn is_spaceless_char(c: char) -> bool {
+    matches!(c,
+        '\u{0E00}'..='\u{0E7F}' // Thai
+        | '\u{3000}'..='\u{303F}' // CJK punctuation
+        | '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK compatibility ideographs
+        | '\u{FF00}'..='\u{FFEF}' // Fullwidth forms
+    )
+}
+
+/// Approximate on-screen width of `c` in Latin-letter units (CJK and Hangul are double)
+fn char_width(c: char) -> usize {
+    let is_wide = matches!(c,
+        '\u{3000}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF00}'..='\u{FFEF}'
+    );
+    if is_wide {
+        2
+    } else {
+        1
+    }
+}
+
+/// Approximate on-screen width of `text` in Latin-letter units
+fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Separator to put between two words when rendering a phrase
+fn word_separator(previous: &str, next: &str) -> &'static str {
+    let joins = previous.chars().last().is_some_and(is_spaceless_char)
+        || next.chars().next().is_some_and(is_spaceless_char);
+    if joins {
+        ""
+    } else {
+        " "
+    }
+}
+
+/// Join words into display text, only putting spaces where the script uses them
+fn join_words(words: &[&TimedWord], render: impl Fn(usize, &TimedWord) -> String) -> String {
+    let mut text = String::new();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            text.push_str(word_separator(&words[i - 1].text, &word.text));
+        }
+        text.push_str(&render(i, word));
+    }
+    text
+}
+
+/// Split words wider than a phrase into character chunks with interpolated timing.
+///
+/// whisper often emits a whole clause of Chinese or Japanese as a single "word" because
+/// there are no spaces to split on. Splitting on `char`s never cuts a character in half.
+fn split_long_words(words: &[TimedWord], max_width: usize) -> Vec<TimedWord> {
+    let mut result = Vec::with_capacity(words.len());
+
+    for word in words {
+        if display_width(&word.text) <= max_width {
+            result.push(word.clone());
+            continue;
+        }
+
+        let mut chunks: Vec<String> = Vec::new();
+        let mut current = String::new();
+        let mut current_width = 0;
+        for c in word.text.chars() {
+            if !current.is_empty() && current_width + char_width(c) > max_width {
+                chunks.push(std::mem::take(&mut current));
+                current_width = 0;
+            }
+            current.push(c);
+            current_width += char_width(c);
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+
+        // Spread the word's duration over the chunks by character count
+        let total_chars = word.text.chars().count() as f64;
+        let duration = word.end - word.start;
+        let mut start = word.start;
+        for chunk in chunks {
+            let end = start + duration * chunk.chars().count() as f64 / total_chars;
+            result.push(TimedWord {
+                text: chunk,
+                start,
+                end,
+            });
+            start = end;
+        }
+    }
 
-    file.write_all(header.as_bytes())?;
+    result
+}
 
-    // Group words into short phrases (2-4 words) for better readability
+/// Group words into short phrases for better readability.
+///
+/// Phrases end at punctuation, after `MAX_WORDS_PER_PHRASE` space-separated words, or
+/// once they reach `MAX_PHRASE_WIDTH`. Width is measured in characters, not bytes, so
+/// multibyte scripts aren't split early.
+fn group_into_phrases(words: &[TimedWord]) -> Vec<Vec<&TimedWord>> {
     let mut phrases: Vec<Vec<&TimedWord>> = Vec::new();
     let mut current_phrase: Vec<&TimedWord> = Vec::new();
-    let max_words_per_phrase = 3;
-    let max_chars_per_phrase = 20;
-    let mut current_chars = 0;
+    let mut current_width = 0;
+    let mut spaced_words = 0;
 
     for word in words {
+        if let Some(previous) = current_phrase.last() {
+            current_width += word_separator(&previous.text, &word.text).len();
+        }
         current_phrase.push(word);
-        current_chars += word.text.len() + 1;
+        current_width += display_width(&word.text);
+        if !word.text.chars().all(is_spaceless_char) {
+            spaced_words += 1;
+        }
 
         // Check for natural breaks (punctuation) or length limits
-        let has_punctuation = word.text.ends_with('.')
-            || word.text.ends_with(',')
-            || word.text.ends_with('?')
-            || word.text.ends_with('!');
-
-        if current_phrase.len() >= max_words_per_phrase
-            || current_chars >= max_chars_per_phrase
+        let has_punctuation = word.text.ends_with([
+            '.', ',', '?', '!', // Latin
+            '。', '、', '？', '！', '，', // CJK
+            '،', '؟', // Arabic
+        ]);
+
+        if spaced_words >= MAX_WORDS_PER_PHRASE
+            || current_width >= MAX_PHRASE_WIDTH
             || has_punctuation
         {
             phrases.push(current_phrase);
             current_phrase = Vec::new();
-            current_chars = 0;
+            current_width = 0;
+            spaced_words = 0;
         }
     }
     if !current_phrase.is_empty() {
         phrases.push(current_phrase);
     }
 
+    phrases
+}
+
+/// Write one karaoke line per phrase, filling each word over its spoken duration
+fn write_karaoke_phrases(file: &mut fs::File, phrases: &[Vec<&TimedWord>], style: &SubtitleStyle) -> Result<()> {
+    for phrase_words in phrases {
+        let (Some(first), Some(last)) = (phrase_words.first(), phrase_words.last()) else {
+            continue;
+        };
+
+        let text = join_words(phrase_words, |i, w| {
+            // Each word fills until the next one starts, so gaps don't stall the sweep
+            let until = phrase_words.get(i + 1).map(|n| n.start).unwrap_or(w.end);
+            let duration_cs = ((until - w.start).max(0.0) * 100.0).round() as u32;
+            format!(
+                "{{\\kf{}}}{}{}",
+                duration_cs,
+                escape_ass_text(&w.text),
+                escape_ass_text(&style.emoji_suffix(&w.text))
+            )
+        });
+
+        let dialogue = format!(
+            "Dialogue: 0,{},{},Default,,0,0,0,,{}\n",
+            format_ass_time(first.start),
+            format_ass_time(last.end + 0.5),
+            text
+        );
+        file.write_all(dialogue.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Generate ASS subtitle with word-by-word highlight animation (TikTok/CapCut style),
+/// or karaoke fill when the style asks for it
+fn generate_ass_with_word_highlight(
+    words: &[TimedWord],
+    output_file: &str,
+    style: &SubtitleStyle,
+) -> Result<()> {
+    let mut file = fs::File::create(output_file)?;
+    file.write_all(ass_header("Word Highlight Subtitles", style).as_bytes())?;
+
+    let words = split_long_words(words, MAX_PHRASE_WIDTH);
+    let phrases = group_into_phrases(&words);
+
+    match style.effect {
+        SubtitleEffect::Karaoke => return write_karaoke_phrases(&mut file, &phrases, style),
+        SubtitleEffect::Static => {
+            // Word timing was only fetched for a sidecar; one plain line per phrase
+            for phrase_words in &phrases {
+                let (Some(first), Some(last)) = (phrase_words.first(), phrase_words.last())
+                else {
+                    continue;
+                };
+                let dialogue = format!(
+                    "Dialogue: 0,{},{},Default,,0,0,0,,{}\n",
+                    format_ass_time(first.start),
+                    format_ass_time(last.end),
+                    join_words(phrase_words, |_, w| escape_ass_text(&style.with_emoji(&w.text)))
+                );
+                file.write_all(dialogue.as_bytes())?;
+            }
+            return Ok(());
+        }
+        SubtitleEffect::WordHighlight => {}
+    }
+
+    let (active_color, spoken_color, upcoming_color) = style.highlight_colors_bgr();
+    let animation = style.highlight_animation;
+    let (spoken_scale, upcoming_scale) = animation.neighbour_scale_tags();
+
     // Generate animated dialogue for each phrase
     for phrase_words in &phrases {
         if phrase_words.is_empty() {
             continue;
         }
 
-        let phrase_start = phrase_words.first().unwrap().start;
         let phrase_end = phrase_words.last().unwrap().end + 0.5;
 
         // For each word in the phrase, create highlight animation
@@ -472,28 +1142,34 @@ Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
             let word_end = word.end;
 
             // Build the text with current word highlighted
-            let mut text = String::new();
-
-            for (i, w) in phrase_words.iter().enumerate() {
+            let text = join_words(phrase_words, |i, w| {
                 if i == word_idx {
-                    // Active word: Yellow, larger, with pop animation
-                    // \t = transform over time, \fscx\fscy = scale
-                    text.push_str(&format!(
-                        "{{\\c&H00FFFF&\\fscx110\\fscy110\\t(0,50,\\fscx100\\fscy100)}}{}{{\\r}}",
-                        w.text
-                    ));
+                    // Active word: highlight color, entering with the style's animation
+                    // Its emoji follows the highlighted run, unanimated
+                    format!(
+                        "{{{}}}{}{{\\r}}{}",
+                        animation.active_tags(&active_color),
+                        escape_ass_text(&w.text),
+                        escape_ass_text(&style.emoji_suffix(&w.text))
+                    )
                 } else if i < word_idx {
                     // Previous words: dimmer white
-                    text.push_str(&format!("{{\\c&HCCCCCC&\\fscx95\\fscy95}}{}", w.text));
+                    format!(
+                        "{{\\c&H{}&{}}}{}",
+                        spoken_color,
+                        spoken_scale,
+                        escape_ass_text(&style.with_emoji(&w.text))
+                    )
                 } else {
                     // Future words: very dim
-                    text.push_str(&format!("{{\\c&H666666&\\fscx90\\fscy90}}{}", w.text));
-                }
-
-                if i < phrase_words.len() - 1 {
-                    text.push(' ');
+                    format!(
+                        "{{\\c&H{}&{}}}{}",
+                        upcoming_color,
+                        upcoming_scale,
+                        escape_ass_text(&style.with_emoji(&w.text))
+                    )
                 }
-            }
+            });
 
             // Write dialogue line for this word's active period
             let dialogue = format!(
@@ -506,13 +1182,10 @@ Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
         }
 
         // Show complete phrase briefly after all words are spoken
-        let mut final_text = String::new();
-        for (i, w) in phrase_words.iter().enumerate() {
-            final_text.push_str(&format!("{{\\c&HFFFFFF&\\fscx100\\fscy100}}{}", w.text));
-            if i < phrase_words.len() - 1 {
-                final_text.push(' ');
-            }
-        }
+        let final_scale = if spoken_scale.is_empty() { "" } else { "\\fscx100\\fscy100" };
+        let final_text = join_words(phrase_words, |_, w| {
+            format!("{{\\c&HFFFFFF&{}}}{}", final_scale, escape_ass_text(&style.with_emoji(&w.text)))
+        });
 
         let last_word_end = phrase_words.last().unwrap().end;
         if phrase_end > last_word_end {
@@ -529,28 +1202,12 @@ Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
     Ok(())
 }
 
-/// Generate simple ASS (fallback when word-level timing not available)
-fn generate_simple_ass(srt_file: &str, output_ass: &str) -> Result<()> {
+/// Generate simple ASS from an SRT file (static styles, or fallback when word-level timing
+/// is not available)
+fn generate_simple_ass(srt_file: &str, output_ass: &str, style: &SubtitleStyle) -> Result<()> {
     let srt_content = fs::read_to_string(srt_file)?;
     let mut file = fs::File::create(output_ass)?;
-
-    // ASS Header - bold, large, with box effect
-    let header = r#"[Script Info]
-Title: Subtitles
-ScriptType: v4.00+
-PlayResX: 720
-PlayResY: 1280
-WrapStyle: 0
-
-[V4+ Styles]
-Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
-Style: Default,Arial Black,38,&H00FFFFFF,&H000000FF,&H00000000,&HAA000000,1,0,0,0,100,100,0,0,4,0,3,2,20,20,100,1
-
-[Events]
-Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
-"#;
-
-    file.write_all(header.as_bytes())?;
+    file.write_all(ass_header("Subtitles", style).as_bytes())?;
 
     // Parse SRT and convert to ASS
     let mut lines_iter = srt_content.lines().peekable();
@@ -568,7 +1225,7 @@ Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
                             lines_iter.next();
                             break;
                         }
-                        text_parts.push(lines_iter.next().unwrap().to_string());
+                        text_parts.push(escape_ass_text(&style.with_emoji(lines_iter.next().unwrap())));
                     }
                     let text = text_parts.join("\\N");
 
@@ -610,110 +1267,513 @@ fn parse_srt_timestamp(line: &str) -> Option<(f64, f64)> {
     Some((parse_time(parts[0])?, parse_time(parts[1])?))
 }
 
-/// Generate subtitle using whisper.cpp with word-level timestamps
-fn generate_subtitle_whisper_cpp(
-    video_file: &str,
-    output_sub: &str,
-    config: &SubtitleConfig,
-) -> Result<()> {
-    let binary = get_whisper_cpp_binary()
-        .ok_or_else(|| anyhow!("whisper.cpp binary not found. Please install it."))?;
+/// One subtitle of an SRT file
+#[derive(Debug, Clone, PartialEq)]
+struct SrtCue {
+    start: f64,
+    end: f64,
+    /// Text lines joined with spaces
+    text: String,
+}
 
-    // Check/download model
+/// Cues of an SRT file, in file order
+fn parse_srt_cues(srt_content: &str) -> Vec<SrtCue> {
+    let mut cues = Vec::new();
+    let mut lines = srt_content.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((start, end)) = parse_srt_timestamp(line) else {
+            continue;
+        };
+        let text: Vec<&str> = lines
+            .by_ref()
+            .take_while(|l| !l.trim().is_empty())
+            .map(str::trim)
+            .collect();
+        cues.push(SrtCue {
+            start,
+            end,
+            text: text.join(" "),
+        });
+    }
+
+    cues
+}
+
+/// Words of an SRT file, with each cue's time spread evenly over its words
+/// (for backends that only produce segment-level timestamps)
+fn parse_srt_words(srt_content: &str) -> Vec<TimedWord> {
+    let mut words = Vec::new();
+
+    for cue in parse_srt_cues(srt_content) {
+        let text: Vec<&str> = cue.text.split_whitespace().collect();
+        let word_duration = (cue.end - cue.start) / text.len().max(1) as f64;
+        for (i, word) in text.iter().enumerate() {
+            words.push(TimedWord {
+                text: word.to_string(),
+                start: cue.start + i as f64 * word_duration,
+                end: cue.start + (i + 1) as f64 * word_duration,
+            });
+        }
+    }
+
+    words
+}
+
+/// Name of the ASS style used for the translated line of dual captions
+const TRANSLATION_STYLE: &str = "Translation";
+
+/// Translated text for each original cue.
+///
+/// The two whisper passes segment the speech differently, so every translated cue goes to
+/// the original cue it overlaps most (or the nearest one when it overlaps none).
+fn pair_translations<'a>(original: &[SrtCue], translated: &'a [SrtCue]) -> Vec<Vec<&'a str>> {
+    // Negative when the cues overlap (more negative = more overlap), else the gap between them
+    let distance = |a: &SrtCue, b: &SrtCue| a.start.max(b.start) - a.end.min(b.end);
+
+    let mut lines = vec![Vec::new(); original.len()];
+    for cue in translated {
+        let closest = original
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| distance(a, cue).total_cmp(&distance(b, cue)))
+            .map(|(i, _)| i);
+        if let Some(i) = closest {
+            lines[i].push(cue.text.as_str());
+        }
+    }
+    lines
+}
+
+/// Write dual captions: each original cue with its translation on a second, smaller line
+fn write_dual_ass(
+    original: &[SrtCue],
+    translated: &[SrtCue],
+    output_ass: &str,
+    style: &SubtitleStyle,
+) -> Result<()> {
+    let mut file = fs::File::create(output_ass)?;
+    let styles = [
+        style.to_ass_style_line("Default"),
+        style.translation_style().to_ass_style_line(TRANSLATION_STYLE),
+    ];
+    file.write_all(ass_header_with_styles("Dual Subtitles", &styles).as_bytes())?;
+
+    for (cue, translation) in original.iter().zip(pair_translations(original, translated)) {
+        let mut text = escape_ass_text(&cue.text);
+        if !translation.is_empty() {
+            // \N breaks the line, \r switches to the translation style for the rest
+            text.push_str(&format!(
+                "\\N{{\\r{}}}{}",
+                TRANSLATION_STYLE,
+                escape_ass_text(&translation.join(" "))
+            ));
+        }
+        let dialogue = format!(
+            "Dialogue: 0,{},{},Default,,0,0,0,,{}\n",
+            format_ass_time(cue.start),
+            format_ass_time(cue.end),
+            text
+        );
+        file.write_all(dialogue.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Installed whisper.cpp binaries and the path of the configured model, downloading
+/// the model when it is missing
+fn whisper_cpp_binaries_and_model(config: &SubtitleConfig) -> Result<(Vec<String>, PathBuf)> {
+    let binaries = whisper_cpp_binaries();
+    if binaries.is_empty() {
+        return Err(anyhow!(
+            "whisper.cpp binary not found (looked for {}). Please install it.",
+            WHISPER_CPP_BINARY_NAMES.join(", ")
+        ));
+    }
     if !check_whisper_model_exists(config.model) {
-        println!("  Model not found. Downloading...");
+        info!("  Model not found. Downloading...");
         download_whisper_model(config.model)?;
     }
+    Ok((binaries, get_whisper_cpp_models_dir().join(config.model.ggml_filename())))
+}
 
-    let model_path = get_whisper_cpp_models_dir().join(config.model.ggml_filename());
+/// `temp_dir/{stem of video_file}_{suffix}`, the base name of intermediate transcription
+/// files, so nothing is written next to the input
+fn transcription_base(video_file: &str, temp_dir: &Path, suffix: impl std::fmt::Display) -> String {
+    let stem = Path::new(video_file).file_stem().unwrap_or_default().to_string_lossy();
+    temp_dir.join(format!("{}_{}", stem, suffix)).to_string_lossy().to_string()
+}
 
-    // Extract audio first (whisper.cpp works with audio files)
-    let audio_file = format!("{}.wav", video_file.trim_end_matches(".mp4"));
-    println!("  Extracting audio...");
-    extract_audio(video_file, &audio_file)?;
+/// Segment-level cues of `video_file` for `config.task`, from the configured backend's SRT
+fn transcribe_cues(
+    video_file: &str,
+    temp_dir: &Path,
+    config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
+) -> Result<Vec<SrtCue>> {
+    let base = transcription_base(video_file, temp_dir, config.task);
+    let srt_file = format!("{}.srt", base);
 
-    let output_base = output_sub
-        .trim_end_matches(".ass")
-        .trim_end_matches(".srt");
+    match config.backend {
+        SubtitleBackend::WhisperCpp => {
+            let (binaries, model_path) = whisper_cpp_binaries_and_model(config)?;
+            let audio_file = format!("{}.wav", base);
+            extract_audio(video_file, &audio_file, &config.ffmpeg(), runner)?;
+            let transcribed = transcribe_with_binaries(&binaries, |binary| {
+                let output = runner.output(
+                    Command::new(binary)
+                        .args(["-m", &model_path.to_string_lossy()])
+                        .args(["-f", &audio_file])
+                        .args(["-l", &config.language])
+                        .args(whisper_cpp_decoding_args(config))
+                        .arg("--output-srt")
+                        .args(["-of", &base]),
+                )?;
+                if !output.success {
+                    return Err(anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+                }
+                Ok(())
+            });
+            let _ = fs::remove_file(&audio_file);
+            transcribed?;
+        }
+        SubtitleBackend::FasterWhisper => {
+            generate_subtitle_faster_whisper(video_file, &srt_file, config, runner)?;
+        }
+    }
 
-    println!(
-        "  Transcribing with whisper.cpp ({}) - word-level...",
-        config.model
-    );
+    let content = fs::read_to_string(&srt_file);
+    let _ = fs::remove_file(&srt_file);
+    Ok(parse_srt_cues(&content?))
+}
 
-    // Use --output-json-full for detailed word timestamps
-    // Use --split-on-word for word-level splitting
-    // Use --max-len 1 for very short segments
-    let output = Command::new(&binary)
-        .args(["-m", &model_path.to_string_lossy()])
-        .args(["-f", &audio_file])
-        .args(["-l", &config.language])
-        .args(["--output-json-full"]) // Full JSON with token timestamps
-        .args(["--split-on-word"]) // Split on word boundaries
-        .args(["--max-len", "1"]) // Very short segments for precise timing
-        .args(["-of", output_base])
-        .output()?;
-
-    let json_file = format!("{}.json", output_base);
-    let ass_file = format!("{}.ass", output_base);
-
-    if output.status.success() && std::path::Path::new(&json_file).exists() {
-        // Parse JSON and generate word-highlight ASS
-        println!("  Generating word-by-word highlight subtitles...");
-        match parse_whisper_json(&json_file) {
-            Ok(words) if !words.is_empty() => {
-                println!("  Found {} words with timestamps", words.len());
-                generate_ass_with_word_highlight(&words, &ass_file)?;
-                let _ = fs::remove_file(&json_file);
-                let _ = fs::remove_file(&audio_file);
+/// Dual captions for `video_file` (see `SubtitleConfig::dual`): whisper transcribes, then
+/// translates, and both are written into one ASS file
+fn generate_dual_subtitle(
+    video_file: &str,
+    output_ass: &str,
+    config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    // Intermediate files go beside the output, in the temp dir
+    let temp_dir = Path::new(output_ass).parent().unwrap_or(Path::new("."));
+    info!("  Transcribing for dual captions...");
+    let original = transcribe_cues(video_file, temp_dir, &config.clone().with_task(WhisperTask::Transcribe), runner)?;
+    info!("  Translating for dual captions...");
+    let translated = transcribe_cues(video_file, temp_dir, &config.clone().with_task(WhisperTask::Translate), runner)?;
+    if original.is_empty() {
+        return Err(anyhow!("No speech found for dual captions"));
+    }
+    write_dual_ass(&original, &translated, output_ass, config.style_for_language(None))?;
+    info!("  Dual captions generated ({} lines)!", original.len());
+    Ok(())
+}
 
-                // Rename to expected output
-                if ass_file != output_sub {
-                    fs::rename(&ass_file, output_sub)?;
+/// Transcribe `video_file` into timed words with the configured backend, writing
+/// intermediate files into `temp_dir`.
+///
+/// Unlike `generate_subtitle` this runs even when subtitles are disabled, for callers that
+/// search the transcript rather than burn it. whisper.cpp gives word-level timestamps;
+/// faster-whisper's segment timestamps are spread over each segment's words.
+pub fn transcribe_words(
+    video_file: &str,
+    temp_dir: &Path,
+    config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
+) -> Result<Vec<TimedWord>> {
+    let base = transcription_base(video_file, temp_dir, "words");
+
+    match config.backend {
+        SubtitleBackend::WhisperCpp => {
+            let (binaries, model_path) = whisper_cpp_binaries_and_model(config)?;
+
+            let audio_file = format!("{}.wav", base);
+            extract_audio(video_file, &audio_file, &config.ffmpeg(), runner)?;
+            let words = transcribe_with_binaries(&binaries, |binary| {
+                let output = runner.output(
+                    Command::new(binary)
+                        .args(["-m", &model_path.to_string_lossy()])
+                        .args(["-f", &audio_file])
+                        .args(["-l", &config.language])
+                        .args(whisper_cpp_decoding_args(config))
+                        .args(["--output-json-full", "--split-on-word", "--max-len", "1"])
+                        .args(["-of", &base]),
+                )?;
+                log_detected_language(config, &output);
+                if !output.success {
+                    return Err(anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
                 }
-                println!("  Word-highlight subtitles generated!");
-                return Ok(());
-            }
-            Ok(_) => {
-                println!("  No words found in JSON, falling back...");
-            }
-            Err(e) => {
-                println!("  Word-level parsing failed: {}, falling back...", e);
-            }
+                let json_file = format!("{}.json", base);
+                let words = parse_whisper_json(&json_file);
+                let _ = fs::remove_file(&json_file);
+                words
+            });
+            let _ = fs::remove_file(&audio_file);
+            words
+        }
+        SubtitleBackend::FasterWhisper => {
+            let srt_file = format!("{}.srt", base);
+            generate_subtitle_faster_whisper(video_file, &srt_file, config, runner)?;
+            let content = fs::read_to_string(&srt_file);
+            let _ = fs::remove_file(&srt_file);
+            Ok(parse_srt_words(&content?))
         }
-        let _ = fs::remove_file(&json_file);
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        println!("  JSON generation failed: {}", stderr);
     }
+}
 
-    // Fallback: generate SRT and convert to styled ASS
-    println!("  Falling back to standard subtitles...");
+/// Generate subtitle using whisper.cpp, trying each installed binary in turn
+fn generate_subtitle_whisper_cpp(
+    video_file: &str,
+    output_sub: &str,
+    config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    // Extract audio first (whisper.cpp works with audio files, and it keys the transcript cache).
+    // It goes beside the subtitle, in the temp dir, rather than next to the input video
+    let audio_file = Path::new(output_sub).with_extension("wav").to_string_lossy().to_string();
+    info!("  Extracting audio...");
+    extract_audio(video_file, &audio_file, &config.ffmpeg(), runner)?;
 
-    let output = Command::new(&binary)
-        .args(["-m", &model_path.to_string_lossy()])
-        .args(["-f", &audio_file])
-        .args(["-l", &config.language])
-        .args(["--output-srt"])
-        .args(["-of", output_base])
-        .output()?;
+    let result =
+        whisper_cpp_subtitle_from_audio(&audio_file, output_sub, &transcript_cache_dir(), config, runner);
 
     // Clean up audio file
     let _ = fs::remove_file(&audio_file);
+    result
+}
 
-    if output.status.success() {
+/// Write subtitles for `audio_file` from the transcript cached in `cache_dir`, or transcribe
+/// (and cache) it
+fn whisper_cpp_subtitle_from_audio(
+    audio_file: &str,
+    output_sub: &str,
+    cache_dir: &Path,
+    config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    let cache_key = fs::read(audio_file)
+        .ok()
+        .map(|audio| transcript::cache_key(&audio, config));
+
+    if config.transcript_cache != TranscriptCache::Refresh {
+        let cached = cache_key
+            .as_deref()
+            .and_then(|key| transcript::load_words(cache_dir, key));
+        if let Some(words) = cached {
+            info!("  Restyling cached transcript ({} words)...", words.len());
+            return write_word_subtitles(&words, output_sub, config, config.style_for_language(None));
+        }
+        if config.transcript_cache == TranscriptCache::Only {
+            return Err(anyhow!(
+                "No cached transcript for this clip (run once without --restyle to create it)"
+            ));
+        }
+    }
+
+    let (binaries, model_path) = whisper_cpp_binaries_and_model(config)?;
+
+    if config.vad && !get_whisper_cpp_models_dir().join(WHISPER_VAD_MODEL).exists() {
+        info!(
+            "  VAD needs {} in {} (download: {}); transcribing without VAD.",
+            WHISPER_VAD_MODEL,
+            get_whisper_cpp_models_dir().display(),
+            WHISPER_VAD_MODEL_URL
+        );
+    }
+
+    let model_path = model_path.to_string_lossy();
+    let words = transcribe_with_binaries(&binaries, |binary| {
+        transcribe_whisper_cpp(binary, &model_path, audio_file, output_sub, config, runner)
+    })?;
+
+    // Only word-level transcripts are cached; SRT fallbacks have no word timing to reuse
+    if let (Some(key), Some(words)) = (cache_key, words) {
+        if let Err(e) = transcript::save_words(cache_dir, &key, &words) {
+            tracing::warn!("  Could not cache transcript: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Write the ASS subtitles (and a WebVTT sidecar when configured) for timed words
+fn write_word_subtitles(
+    words: &[TimedWord],
+    output_sub: &str,
+    config: &SubtitleConfig,
+    style: &SubtitleStyle,
+) -> Result<()> {
+    if config.sidecar == Some(SubtitleFormat::Vtt) {
+        let output_base = output_sub.trim_end_matches(".ass").trim_end_matches(".srt");
+        generate_vtt_with_word_timing(words, &format!("{}.vtt", output_base), style)?;
+    }
+    generate_ass_with_word_highlight(words, output_sub, style)
+}
+
+/// Run `transcribe` with each whisper.cpp binary until one succeeds.
+///
+/// A binary that exists but is broken (a stub, wrong architecture, missing libraries)
+/// shouldn't end transcription while another install works. The error lists every binary
+/// tried with its stderr.
+fn transcribe_with_binaries<T>(
+    binaries: &[String],
+    mut transcribe: impl FnMut(&str) -> Result<T>,
+) -> Result<T> {
+    let mut failures = Vec::new();
+
+    for binary in binaries {
+        match transcribe(binary) {
+            Ok(words) => return Ok(words),
+            Err(e) => {
+                tracing::warn!("  {} failed, trying the next whisper.cpp binary...", binary);
+                failures.push(format!("  {}: {}", binary, e.to_string().trim()));
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "whisper.cpp failed with every binary tried:\n{}",
+        failures.join("\n")
+    ))
+}
+
+/// whisper.cpp arguments for the configured threads, beam size, temperature, task and VAD.
+///
+/// VAD is left out when the Silero model is not in the models directory.
+fn whisper_cpp_decoding_args(config: &SubtitleConfig) -> Vec<String> {
+    let mut args = vec![
+        "-t".to_string(),
+        config.thread_count().to_string(),
+        "--beam-size".to_string(),
+        config.beam_size.to_string(),
+        "--temperature".to_string(),
+        config.temperature.to_string(),
+    ];
+    if config.task == WhisperTask::Translate {
+        args.push("--translate".to_string());
+    }
+    let vad_model = get_whisper_cpp_models_dir().join(WHISPER_VAD_MODEL);
+    if config.vad && vad_model.exists() {
+        args.extend([
+            "--vad".to_string(),
+            "--vad-model".to_string(),
+            vad_model.to_string_lossy().to_string(),
+        ]);
+    }
+    args
+}
+
+/// Whether whisper.cpp rejected a command-line option, as older builds do for
+/// `--output-json-full`
+fn is_unsupported_option_error(stderr: &str, option: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    stderr.contains(option.trim_start_matches('-'))
+        && ["unknown", "unrecognized", "invalid", "not supported"]
+            .iter()
+            .any(|hint| stderr.contains(hint))
+}
+
+/// Transcribe `audio_file` with one whisper.cpp binary into `output_sub` (ASS).
+///
+/// Returns the words when word-level timestamps were available.
+fn transcribe_whisper_cpp(
+    binary: &str,
+    model_path: &str,
+    audio_file: &str,
+    output_sub: &str,
+    config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
+) -> Result<Option<Vec<TimedWord>>> {
+    let output_base = output_sub
+        .trim_end_matches(".ass")
+        .trim_end_matches(".srt");
+
+    info!(
+        "  Transcribing with whisper.cpp ({}, {}) - word-level...",
+        binary, config.model
+    );
+
+    // Static styles don't need word timing unless a WebVTT sidecar wants it, go straight to SRT
+    if config.wants_word_timing() {
+        // Use --output-json-full for detailed word timestamps
+        // Use --split-on-word for word-level splitting
+        // Use --max-len 1 for very short segments
+        let output = runner.output(
+            Command::new(binary)
+                .args(["-m", model_path])
+                .args(["-f", audio_file])
+                .args(["-l", &config.language])
+                .args(whisper_cpp_decoding_args(config))
+                .args(["--output-json-full"]) // Full JSON with token timestamps
+                .args(["--split-on-word"]) // Split on word boundaries
+                .args(["--max-len", "1"]) // Very short segments for precise timing
+                .args(["-of", output_base]),
+        )?;
+
+        let json_file = format!("{}.json", output_base);
+
+        let detected = log_detected_language(config, &output);
+
+        if output.success && std::path::Path::new(&json_file).exists() {
+            // Parse JSON and generate word-highlight ASS
+            info!("  Generating word-by-word highlight subtitles...");
+            match parse_whisper_json(&json_file) {
+                Ok(words) if !words.is_empty() => {
+                    info!("  Found {} words with timestamps", words.len());
+                    let _ = fs::remove_file(&json_file);
+                    write_word_subtitles(&words, output_sub, config, config.style_for_language(detected.as_deref()))?;
+                    info!("  Word-highlight subtitles generated!");
+                    return Ok(Some(words));
+                }
+                Ok(_) => {
+                    info!("  No words found in JSON, falling back...");
+                }
+                Err(e) => {
+                    tracing::warn!("  Word-level parsing failed: {}, falling back...", e);
+                }
+            }
+            let _ = fs::remove_file(&json_file);
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if is_unsupported_option_error(&stderr, "--output-json-full") {
+                info!(
+                    "  {} does not support --output-json-full (older whisper.cpp build); \
+                     word highlighting needs a newer release.",
+                    binary
+                );
+            } else {
+                tracing::warn!("  JSON generation failed: {}", stderr.trim());
+            }
+        }
+
+        info!("  Falling back to standard subtitles...");
+    }
+
+    // Generate SRT and convert to styled ASS
+    let output = runner.output(
+        Command::new(binary)
+            .args(["-m", model_path])
+            .args(["-f", audio_file])
+            .args(["-l", &config.language])
+            .args(whisper_cpp_decoding_args(config))
+            .args(["--output-srt"])
+            .args(["-of", output_base]),
+    )?;
+
+    if output.success {
+        let detected = log_detected_language(config, &output);
         let srt_file = format!("{}.srt", output_base);
         if std::path::Path::new(&srt_file).exists() {
-            generate_simple_ass(&srt_file, output_sub)?;
+            generate_simple_ass(&srt_file, output_sub, config.style_for_language(detected.as_deref()))?;
             let _ = fs::remove_file(&srt_file);
-            println!("  Styled subtitles generated!");
-            Ok(())
+            info!("  Styled subtitles generated!");
+            Ok(None)
         } else {
             Err(anyhow!("SRT file not created"))
         }
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(anyhow!("whisper.cpp failed: {}", stderr))
+        Err(anyhow!("{}", stderr.trim()))
     }
 }
 
@@ -759,7 +1819,7 @@ fn get_python_executable() -> &'static str {
 
 /// Install faster-whisper if not available
 pub fn install_faster_whisper() -> Result<()> {
-    println!("  Installing faster-whisper...");
+    info!("  Installing faster-whisper...");
     let python = get_python_executable();
 
     let status = Command::new(python)
@@ -767,7 +1827,7 @@ pub fn install_faster_whisper() -> Result<()> {
         .status()?;
 
     if status.success() {
-        println!("  faster-whisper installed successfully.");
+        info!("  faster-whisper installed successfully.");
         Ok(())
     } else {
         Err(anyhow!("Failed to install faster-whisper"))
@@ -779,15 +1839,21 @@ fn generate_subtitle_faster_whisper(
     video_file: &str,
     output_srt: &str,
     config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
 ) -> Result<()> {
     if !check_faster_whisper_available() {
-        println!("  faster-whisper not found. Installing...");
+        info!("  faster-whisper not found. Installing...");
         install_faster_whisper()?;
     }
 
     let python = get_python_executable();
     let model_name = config.model.to_string();
-    let language = &config.language;
+    // None lets faster-whisper detect the language
+    let language = if config.is_auto_language() {
+        "None".to_string()
+    } else {
+        format!("\"{}\"", config.language)
+    };
 
     let python_script = format!(
         r#"
@@ -797,13 +1863,22 @@ from faster_whisper import WhisperModel
 video_file = "{video_file}"
 output_srt = "{output_srt}"
 model_name = "{model_name}"
-language = "{language}"
+language = {language}
 
 print(f"Loading Whisper model '{{model_name}}'...")
 model = WhisperModel(model_name, device="cpu", compute_type="int8")
 
 print("Transcribing audio...")
-segments, info = model.transcribe(video_file, language=language)
+segments, info = model.transcribe(
+    video_file,
+    language=language,
+    task="{task}",
+    beam_size={beam_size},
+    temperature={temperature:?},
+    vad_filter={vad_filter},
+)
+if language is None:
+    print(f"auto-detected language: {{info.language}} (p = {{info.language_probability:.2f}})")
 
 def format_timestamp(seconds):
     hours = int(seconds // 3600)
@@ -828,20 +1903,25 @@ print("Subtitle generated successfully.")
         output_srt = output_srt.replace('\\', "\\\\").replace('"', "\\\""),
         model_name = model_name,
         language = language,
+        task = config.task,
+        beam_size = config.beam_size,
+        temperature = config.temperature,
+        vad_filter = if config.vad { "True" } else { "False" },
     );
 
-    println!(
+    info!(
         "  Generating subtitle with faster-whisper ({})...",
         model_name
     );
 
-    let output = Command::new(python).args(["-c", &python_script]).output()?;
+    let output = runner.output(Command::new(python).args(["-c", &python_script]))?;
 
-    if output.status.success() {
+    if output.success {
         let stdout = String::from_utf8_lossy(&output.stdout);
         for line in stdout.lines() {
-            println!("  {}", line);
+            info!("  {}", line);
         }
+        log_detected_language(config, &output);
         Ok(())
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -854,139 +1934,377 @@ pub fn generate_subtitle(
     video_file: &str,
     output_srt: &str,
     config: &SubtitleConfig,
+    runner: &dyn CommandRunner,
 ) -> Result<()> {
     if !config.enabled {
         return Ok(());
     }
+    // Only single-language whisper.cpp transcripts are cached, so anything else would transcribe
+    if config.transcript_cache == TranscriptCache::Only
+        && (config.dual || config.backend != SubtitleBackend::WhisperCpp)
+    {
+        return Err(anyhow!(
+            "--restyle needs cached transcripts, which only whisper.cpp keeps (and not for --dual)"
+        ));
+    }
+    if config.dual {
+        return generate_dual_subtitle(video_file, output_srt, config, runner);
+    }
 
     match config.backend {
         SubtitleBackend::WhisperCpp => {
-            generate_subtitle_whisper_cpp(video_file, output_srt, config)
+            generate_subtitle_whisper_cpp(video_file, output_srt, config, runner)
         }
         SubtitleBackend::FasterWhisper => {
-            generate_subtitle_faster_whisper(video_file, output_srt, config)
+            generate_subtitle_faster_whisper(video_file, output_srt, config, runner)
         }
     }
 }
 
 /// Burn subtitle onto video using FFmpeg
-pub fn burn_subtitle(video_file: &str, sub_file: &str, output_file: &str, use_gpu: bool) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn burn_subtitle(
+    video_file: &str,
+    sub_file: &str,
+    output_file: &str,
+    style: &SubtitleStyle,
+    use_gpu: bool,
+    faststart: bool,
+    ffmpeg: &Path,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
     let abs_sub_path = std::path::Path::new(sub_file)
         .canonicalize()
         .unwrap_or_else(|_| std::path::PathBuf::from(sub_file));
 
-    // FFmpeg subtitle filter needs special escaping on Windows
-    let subtitle_path = abs_sub_path
-        .to_string_lossy()
-        .replace('\\', "/")
-        .replace(':', "\\:");
+    // Escape for the filtergraph (handles Windows drive colons, quotes, commas, ...)
+    let subtitle_path = escape_filter_path(&abs_sub_path.to_string_lossy());
 
     // Detect if it's ASS or SRT based on extension
     let is_ass = sub_file.ends_with(".ass");
 
     let subtitle_filter = if is_ass {
         // For ASS files, use ass filter (preserves styling including karaoke effects)
-        format!("ass='{}'", subtitle_path)
+        format!("ass={}", subtitle_path)
     } else {
         // For SRT files, use subtitles filter with styling
         format!(
-            "subtitles='{}':force_style='FontName=Arial Black,FontSize=42,Bold=1,\
+            "subtitles={}:force_style='FontName=Arial Black,FontSize=42,Bold=1,\
             PrimaryColour=&H00FFFFFF,OutlineColour=&H00000000,BackColour=&H80000000,\
-            BorderStyle=1,Outline=3,Shadow=2,MarginV=120'",
-            subtitle_path
+            BorderStyle=1,Outline=3,Shadow=2,Alignment={},MarginV=120'",
+            subtitle_path,
+            style.position.alignment()
         )
     };
 
-    println!("  Burning subtitle to video...");
+    info!("  Burning subtitle to video...");
 
     // Choose encoder based on GPU flag
-    let mut cmd = Command::new("ffmpeg");
+    let mut cmd = Command::new(ffmpeg);
     cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
         .args(["-i", video_file])
         .args(["-vf", &subtitle_filter]);
 
     if use_gpu {
-        println!("  Using GPU encoder (NVENC)...");
+        info!("  Using GPU encoder (NVENC)...");
         cmd.args(["-c:v", "h264_nvenc", "-preset", "p4", "-rc", "vbr", "-cq", "26"]);
     } else {
         cmd.args(["-c:v", "libx264", "-preset", "ultrafast", "-crf", "26"]);
     }
 
-    let status = cmd.args(["-c:a", "copy"])
-        .arg(output_file)
-        .status()?;
+    cmd.args(["-c:a", "copy"])
+        .args(crate::faststart_args(faststart, output_file))
+        .arg(output_file);
 
-    if status.success() {
+    if runner.run(&mut cmd, None)? == Some(true) {
         Ok(())
     } else {
         Err(anyhow!("Failed to burn subtitle to video"))
     }
 }
 
-/// Process subtitle for a video clip
+/// Subtitle codec the output container can hold: Matroska keeps ASS/SRT as they are,
+/// MP4/MOV need `mov_text`
+fn embedded_subtitle_codec(output_file: &str, sub_file: &str) -> &'static str {
+    let is_mkv = output_file.to_lowercase().ends_with(".mkv");
+    match (is_mkv, sub_file.to_lowercase().ends_with(".ass")) {
+        (true, true) => "ass",
+        (true, false) => "srt",
+        (false, _) => "mov_text",
+    }
+}
+
+/// Mux `sub_file` into the video as a caption track (`mov_text` for MP4/MOV), copying audio and video.
+pub fn embed_subtitle(
+    video_file: &str,
+    sub_file: &str,
+    output_file: &str,
+    faststart: bool,
+    ffmpeg: &Path,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    info!("  Embedding subtitle track...");
+
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+        .args(["-i", video_file])
+        .args(["-i", sub_file])
+        .args(["-map", "0:v", "-map", "0:a?", "-map", "1:s"])
+        .args(["-c:v", "copy", "-c:a", "copy", "-c:s", embedded_subtitle_codec(output_file, sub_file)])
+        .args(crate::faststart_args(faststart, output_file))
+        .arg(output_file);
+
+    if runner.run(&mut cmd, None)? == Some(true) {
+        Ok(())
+    } else {
+        Err(anyhow!("Failed to embed subtitle track"))
+    }
+}
+
+/// Process subtitle for a video clip, adding transcription and burn time to `timings`.
+///
+/// `words` is the clip's transcript when an earlier step already made one; it's used
+/// instead of transcribing again. Failures that leave the clip without subtitles are added
+/// to `warnings`.
+#[allow(clippy::too_many_arguments)]
 pub fn process_subtitle(
     cropped_file: &str,
     output_file: &str,
     config: &SubtitleConfig,
+    words: Option<&[TimedWord]>,
     index: usize,
+    temp_dir: &Path,
     use_gpu: bool,
+    runner: &dyn CommandRunner,
+    timings: &mut StageTimings,
+    warnings: &mut Vec<Warning>,
 ) -> Result<String> {
     if !config.enabled {
-        fs::rename(cropped_file, output_file)?;
+        move_file(cropped_file, output_file)?;
         return Ok(output_file.to_string());
     }
 
-    // Use ASS for whisper.cpp (word-by-word), SRT for faster-whisper
-    let sub_ext = match config.backend {
-        SubtitleBackend::WhisperCpp => "ass",
-        SubtitleBackend::FasterWhisper => "srt",
-    };
-    let sub_file = format!("temp_{}.{}", index, sub_ext);
-
-    match generate_subtitle(cropped_file, &sub_file, config) {
-        Ok(_) => match burn_subtitle(cropped_file, &sub_file, output_file, use_gpu) {
-            Ok(_) => {
-                let _ = fs::remove_file(cropped_file);
-                let _ = fs::remove_file(&sub_file);
-                Ok(output_file.to_string())
-            }
-            Err(e) => {
-                println!(
-                    "  Failed to burn subtitle: {}. Using video without subtitle.",
-                    e
-                );
-                let _ = fs::remove_file(&sub_file);
-                fs::rename(cropped_file, output_file)?;
-                Ok(output_file.to_string())
-            }
-        },
+    let sub_file = temp_dir
+        .join(format!("temp_{}.{}", index, config.subtitle_extension()))
+        .to_string_lossy()
+        .to_string();
+
+    // Caption tracks can't animate, so one cue per line instead of one per word
+    let mut config = config.clone();
+    if config.mode == SubtitleMode::Embed {
+        for style in config.styles_mut() {
+            style.effect = SubtitleEffect::Static;
+        }
+    }
+
+    let generated = profile::timed(&mut timings.transcribe, || match words {
+        // Already transcribed (by silence trimming) at word level, as whisper.cpp would be
+        Some(words) if !words.is_empty() && config.backend == SubtitleBackend::WhisperCpp && !config.dual => {
+            info!("  Reusing the transcript from silence trimming ({} words)...", words.len());
+            write_word_subtitles(words, &sub_file, &config, config.style_for_language(None))
+        }
+        _ => generate_subtitle(cropped_file, &sub_file, &config, runner),
+    });
+    if let Err(e) = generated {
+        warn(
+            warnings,
+            Warning::new(
+                WarningKind::SubtitleFailed,
+                format!("failed to generate subtitle: {}. Continuing without subtitle.", e),
+            )
+            .for_clip(index),
+        );
+        move_file(cropped_file, output_file)?;
+        return Ok(output_file.to_string());
+    }
+
+    if let Some(format) = config.sidecar {
+        keep_sidecar(&sub_file, output_file, format);
+    }
+
+    if config.subtitle_only {
+        keep_subtitle_file(&sub_file, output_file);
+        move_file(cropped_file, output_file)?;
+        return Ok(output_file.to_string());
+    }
+
+    let added = profile::timed(&mut timings.burn, || match config.mode {
+        SubtitleMode::Burn => burn_subtitle(
+            cropped_file,
+            &sub_file,
+            output_file,
+            &config.style,
+            use_gpu,
+            config.faststart,
+            &config.ffmpeg(),
+            runner,
+        ),
+        SubtitleMode::Embed => {
+            embed_subtitle(cropped_file, &sub_file, output_file, config.faststart, &config.ffmpeg(), runner)
+        }
+    });
+    let _ = fs::remove_file(&sub_file);
+
+    match added {
+        Ok(_) => {
+            let _ = fs::remove_file(cropped_file);
+            Ok(output_file.to_string())
+        }
         Err(e) => {
-            println!(
-                "  Failed to generate subtitle: {}. Continuing without subtitle.",
-                e
+            warn(
+                warnings,
+                Warning::new(
+                    WarningKind::SubtitleFailed,
+                    format!("failed to add subtitle: {}. Using video without subtitle.", e),
+                )
+                .for_clip(index),
             );
-            fs::rename(cropped_file, output_file)?;
+            move_file(cropped_file, output_file)?;
             Ok(output_file.to_string())
         }
     }
 }
 
+/// Where `subtitle_local_file` writes its result: `talk.mkv` -> `talk_subtitled.mp4`
+pub fn subtitled_output_path(video_file: &Path) -> std::path::PathBuf {
+    let stem = video_file.file_stem().unwrap_or_default().to_string_lossy();
+    video_file.with_file_name(format!("{}_subtitled.mp4", stem))
+}
+
+/// Transcribe an existing video and add the subtitles according to `config.mode`, writing
+/// the result next to it (see `subtitled_output_path`). The input file is left untouched.
+pub fn subtitle_local_file(
+    video_file: &Path,
+    config: &SubtitleConfig,
+    temp_dir: &Path,
+    use_gpu: bool,
+    runner: &dyn CommandRunner,
+) -> Result<std::path::PathBuf> {
+    if !video_file.is_file() {
+        return Err(anyhow!("Video file not found: {}", video_file.display()));
+    }
+    fs::create_dir_all(temp_dir)?;
+
+    // A local file is burned uncropped, so it gets the default crop's position
+    let mut config = config.clone().for_crop_mode(CropMode::Default);
+    config.enabled = true;
+    if config.mode == SubtitleMode::Embed {
+        for style in config.styles_mut() {
+            style.effect = SubtitleEffect::Static;
+        }
+    }
+
+    let stem = video_file.file_stem().unwrap_or_default().to_string_lossy();
+    let sub_file = temp_dir
+        .join(format!("{}.{}", stem, config.subtitle_extension()))
+        .to_string_lossy()
+        .to_string();
+    let video = video_file.to_string_lossy();
+    let output_path = subtitled_output_path(video_file);
+    let output_file = output_path.to_string_lossy();
+
+    generate_subtitle(&video, &sub_file, &config, runner)?;
+    if let Some(format) = config.sidecar {
+        keep_sidecar(&sub_file, &output_file, format);
+    }
+
+    let added = match config.mode {
+        SubtitleMode::Burn => burn_subtitle(
+            &video,
+            &sub_file,
+            &output_file,
+            &config.style,
+            use_gpu,
+            config.faststart,
+            &config.ffmpeg(),
+            runner,
+        ),
+        SubtitleMode::Embed => {
+            embed_subtitle(&video, &sub_file, &output_file, config.faststart, &config.ffmpeg(), runner)
+        }
+    };
+    let _ = fs::remove_file(&sub_file);
+    added?;
+
+    Ok(output_path)
+}
+
+/// Move the requested sidecar subtitle from the temp directory next to `output_file`
+fn keep_sidecar(sub_file: &str, output_file: &str, format: SubtitleFormat) {
+    let source = Path::new(sub_file).with_extension(format.extension());
+    // The clip may still be under its partial name; the sidecar goes next to the final one
+    let target = crate::finished_path(Path::new(output_file)).with_extension(format.extension());
+    if !source.exists() {
+        // WebVTT needs whisper.cpp word timestamps, ASS is not produced by faster-whisper
+        info!("  No {} sidecar was produced by this backend", format);
+        return;
+    }
+    let kept = match format {
+        // The ASS file is still needed for burning
+        SubtitleFormat::Ass => fs::copy(&source, &target).map(|_| ()),
+        SubtitleFormat::Vtt => move_file(&source, &target),
+    };
+    match kept {
+        Ok(()) => info!("  Saved subtitle sidecar: {}", target.display()),
+        Err(e) => tracing::warn!("  Failed to save subtitle sidecar: {}", e),
+    }
+}
+
+/// Move the generated subtitle next to `output_file`, keeping its format (ASS or SRT)
+fn keep_subtitle_file(sub_file: &str, output_file: &str) {
+    let source = Path::new(sub_file);
+    let extension = source.extension().unwrap_or_default();
+    let target = crate::finished_path(Path::new(output_file)).with_extension(extension);
+    match move_file(source, &target) {
+        Ok(()) => info!("  Saved subtitle (not burned): {}", target.display()),
+        Err(e) => tracing::warn!("  Failed to save subtitle: {}", e),
+    }
+}
+
+/// Availability of the transcription backends
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SubtitleBackendStatus {
+    /// whisper.cpp binary, if found
+    pub whisper_cpp: Option<String>,
+    /// Directory searched for whisper.cpp models
+    pub whisper_models_dir: String,
+    /// whisper.cpp models present in `whisper_models_dir`
+    pub whisper_models: Vec<WhisperModel>,
+    pub python: bool,
+    pub faster_whisper: bool,
+}
+
+/// Detect which transcription backends are usable
+pub fn subtitle_backend_status() -> SubtitleBackendStatus {
+    let python = check_python_available();
+    SubtitleBackendStatus {
+        whisper_cpp: get_whisper_cpp_binary(),
+        whisper_models_dir: get_whisper_cpp_models_dir().to_string_lossy().to_string(),
+        whisper_models: WhisperModel::all()
+            .iter()
+            .copied()
+            .filter(|&model| check_whisper_model_exists(model))
+            .collect(),
+        python,
+        faster_whisper: python && check_faster_whisper_available(),
+    }
+}
+
 /// Print subtitle backend status
 pub fn print_subtitle_status() {
     println!("\n=== Subtitle Backend Status ===");
+    let status = subtitle_backend_status();
 
-    if check_whisper_cpp_available() {
+    if let Some(binary) = &status.whisper_cpp {
         println!("  [OK] whisper.cpp: Available");
-        if let Some(binary) = get_whisper_cpp_binary() {
-            println!("       Binary: {}", binary);
-        }
+        println!("       Binary: {}", binary);
     } else {
         println!("  [--] whisper.cpp: Not found");
         println!("       Download from: https://github.com/ggerganov/whisper.cpp/releases");
     }
 
-    if check_python_available() {
-        if check_faster_whisper_available() {
+    if status.python {
+        if status.faster_whisper {
             println!("  [OK] faster-whisper: Available");
         } else {
             println!("  [--] faster-whisper: Not installed (run: pip install faster-whisper)");
@@ -1007,13 +2325,486 @@ mod tests {
         assert_eq!(WhisperModel::from_input("tiny"), Some(WhisperModel::Tiny));
         assert_eq!(WhisperModel::from_input("small"), Some(WhisperModel::Small));
         assert_eq!(WhisperModel::from_input("large"), Some(WhisperModel::Large));
+        assert_eq!(WhisperModel::from_input("large-v3"), Some(WhisperModel::Large));
+        assert_eq!(WhisperModel::from_input("Turbo"), Some(WhisperModel::LargeV3Turbo));
+        assert_eq!(WhisperModel::from_input("large-v3-turbo"), Some(WhisperModel::LargeV3Turbo));
+        assert_eq!(WhisperModel::from_input("distil-small"), Some(WhisperModel::DistilSmallEn));
+        assert_eq!(WhisperModel::from_input("distil-large-v3"), Some(WhisperModel::DistilLargeV3));
         assert_eq!(WhisperModel::from_input("invalid"), None);
+
+        // Display and serde names are the canonical ones, which parse back
+        for &model in WhisperModel::all() {
+            assert_eq!(WhisperModel::from_input(&model.to_string()), Some(model));
+            assert_eq!(serde_json::to_value(model).unwrap(), model.to_string());
+        }
+        assert_eq!(WhisperModel::LargeV3Turbo.to_string(), "large-v3-turbo");
     }
 
     #[test]
     fn test_ggml_filename() {
         assert_eq!(WhisperModel::Small.ggml_filename(), "ggml-small.bin");
         assert_eq!(WhisperModel::Large.ggml_filename(), "ggml-large.bin");
+        assert_eq!(WhisperModel::LargeV3Turbo.ggml_filename(), "ggml-large-v3-turbo.bin");
+        assert_eq!(
+            WhisperModel::LargeV3Turbo.download_url(),
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin"
+        );
+        assert_eq!(
+            WhisperModel::DistilSmallEn.download_url(),
+            "https://huggingface.co/distil-whisper/distil-small.en/resolve/main/ggml-distil-small.en.bin"
+        );
+    }
+
+    #[test]
+    fn test_burn_subtitle_uses_ass_filter() {
+        let runner = crate::runner::RecordingRunner::new();
+        let style = SubtitleStyle::default();
+        burn_subtitle("in.mp4", "/tmp/sub.ass", "out.mp4", &style, false, true, Path::new("ffmpeg"), &runner).unwrap();
+
+        let call = &runner.calls()[0];
+        assert_eq!(call[0], "ffmpeg");
+        let vf = call.iter().position(|a| a == "-vf").unwrap();
+        assert_eq!(call[vf + 1], "ass=/tmp/sub.ass");
+        assert!(call.contains(&"libx264".to_string()));
+        assert_eq!(call[call.len() - 3..], ["-movflags", "+faststart", "out.mp4"]);
+    }
+
+    #[test]
+    fn test_suggested_model_for_language() {
+        let config = SubtitleConfig::default();
+        assert_eq!(config.suggested_model(), None);
+
+        let tiny = SubtitleConfig::new(true, WhisperModel::Tiny, "id");
+        assert_eq!(tiny.suggested_model(), Some(WhisperModel::Small));
+        let auto = SubtitleConfig::new(true, WhisperModel::Base, "auto");
+        assert_eq!(auto.suggested_model(), Some(WhisperModel::Small));
+        let english = SubtitleConfig::new(true, WhisperModel::Tiny, "en");
+        assert_eq!(english.suggested_model(), None);
+        let large = SubtitleConfig::new(true, WhisperModel::Large, "ja");
+        assert_eq!(large.suggested_model(), None);
+
+        // Distil models are English-only, however large
+        let distil = SubtitleConfig::new(true, WhisperModel::DistilLargeV3, "ja");
+        assert_eq!(distil.suggested_model(), Some(WhisperModel::LargeV3Turbo));
+        let distil = SubtitleConfig::new(true, WhisperModel::DistilSmallEn, "en");
+        assert_eq!(distil.suggested_model(), None);
+    }
+
+    #[test]
+    fn test_language_style_is_chosen() {
+        let cjk = SubtitleStyle {
+            font_name: "Noto Sans CJK JP".to_string(),
+            ..SubtitlePreset::default().to_style()
+        };
+        let default_font = SubtitleConfig::default().style.font_name;
+
+        let japanese = SubtitleConfig::new(true, WhisperModel::Small, "ja").with_language_style("JA", cjk.clone());
+        assert_eq!(japanese.style_for_language(None).font_name, "Noto Sans CJK JP");
+        // A configured language wins over whatever whisper reports
+        assert_eq!(japanese.style_for_language(Some("en")).font_name, "Noto Sans CJK JP");
+
+        let english = SubtitleConfig::new(true, WhisperModel::Small, "en").with_language_style("ja", cjk.clone());
+        assert_eq!(english.style_for_language(None).font_name, default_font);
+
+        let auto = SubtitleConfig::new(true, WhisperModel::Small, "auto")
+            .with_language_style("ja", cjk)
+            .for_crop_mode(CropMode::Square);
+        assert_eq!(auto.style_for_language(Some("ja")).font_name, "Noto Sans CJK JP");
+        assert_eq!(auto.style_for_language(Some("ja")).position, auto.style.position);
+        assert_eq!(auto.style_for_language(Some("en")).font_name, default_font);
+        assert_eq!(auto.style_for_language(None).font_name, default_font);
+    }
+
+    #[test]
+    fn test_subtitle_position_per_crop_mode() {
+        use crate::crop::Corner;
+
+        let alignment = |config: &SubtitleConfig, mode| config.clone().for_crop_mode(mode).style.position.alignment();
+        let config = SubtitleConfig::default();
+        assert_eq!(alignment(&config, CropMode::Default), 2);
+        assert_eq!(alignment(&config, CropMode::Square), 2);
+        assert_eq!(alignment(&config, CropMode::MotionTrack { offset: None }), 2);
+        assert_eq!(alignment(&config, CropMode::Split { facecam: Corner::BottomLeft }), 5);
+        assert_eq!(alignment(&config, CropMode::Split { facecam: Corner::TopRight }), 5);
+
+        // An explicit position wins over the crop mode's default
+        let config = config.with_position(Some(SubtitlePosition::Bottom));
+        assert_eq!(alignment(&config, CropMode::Split { facecam: Corner::BottomLeft }), 2);
+    }
+
+    #[test]
+    fn test_burn_srt_honors_position() {
+        let runner = crate::runner::RecordingRunner::new();
+        let style = SubtitleStyle {
+            position: SubtitlePosition::Top,
+            ..Default::default()
+        };
+        burn_subtitle("in.mp4", "/tmp/sub.srt", "out.mp4", &style, false, false, Path::new("ffmpeg"), &runner).unwrap();
+
+        let call = &runner.calls()[0];
+        assert!(!call.contains(&"-movflags".to_string()));
+        let vf = call.iter().position(|a| a == "-vf").unwrap();
+        assert!(call[vf + 1].contains("Alignment=8,"));
+    }
+
+    #[test]
+    fn test_embed_subtitle_copies_streams() {
+        let runner = crate::runner::RecordingRunner::new();
+        embed_subtitle("in.mp4", "/tmp/sub.srt", "out.mp4", true, Path::new("ffmpeg"), &runner).unwrap();
+
+        let call = &runner.calls()[0];
+        assert!(!call.contains(&"-vf".to_string()));
+        let codecs = call.iter().position(|a| a == "-c:v").unwrap();
+        assert_eq!(
+            &call[codecs..codecs + 6],
+            ["-c:v", "copy", "-c:a", "copy", "-c:s", "mov_text"]
+        );
+        assert_eq!(call.last().unwrap(), "out.mp4");
+    }
+
+    #[test]
+    fn test_parse_srt_words() {
+        let srt = "1\n00:00:01,000 --> 00:00:03,000\nhello big\nworld\n\n\
+                   2\n00:00:05,000 --> 00:00:06,000\nagain\n";
+        let words = parse_srt_words(srt);
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, ["hello", "big", "world", "again"]);
+        assert!((words[1].start - 1.0 - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!((words[3].start, words[3].end), (5.0, 6.0));
+    }
+
+    #[test]
+    fn test_restyle_from_cached_transcript() {
+        let dir = std::env::temp_dir().join("yt-clipper-test-restyle");
+        let _ = fs::create_dir_all(&dir);
+        let cache_dir = dir.join("cache");
+        let audio = dir.join("clip.wav").to_string_lossy().to_string();
+        let output = dir.join("clip.ass").to_string_lossy().to_string();
+        fs::write(&audio, b"RIFF fake audio").unwrap();
+
+        let runner = crate::runner::RecordingRunner::new();
+        let config = SubtitleConfig::default().with_transcript_cache(TranscriptCache::Only);
+        assert!(whisper_cpp_subtitle_from_audio(&audio, &output, &cache_dir, &config, &runner).is_err());
+
+        let words = vec![TimedWord {
+            text: "halo".to_string(),
+            start: 1.0,
+            end: 1.5,
+        }];
+        let key = transcript::cache_key(b"RIFF fake audio", &config);
+        transcript::save_words(&cache_dir, &key, &words).unwrap();
+        whisper_cpp_subtitle_from_audio(&audio, &output, &cache_dir, &config, &runner).unwrap();
+
+        let ass = fs::read_to_string(&output).unwrap();
+
+        // faster-whisper keeps no cache, so restyling with it must not transcribe
+        let mut faster = config.clone().with_backend(SubtitleBackend::FasterWhisper);
+        faster.enabled = true;
+        assert!(generate_subtitle(&audio, &output, &faster, &runner).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+        assert!(ass.contains("halo"));
+        assert!(runner.calls().is_empty());
+    }
+
+    #[test]
+    fn test_emoji_map_inserted_after_word() {
+        let words = vec![
+            TimedWord { text: "this".to_string(), start: 0.0, end: 0.4 },
+            TimedWord { text: "FIRE!".to_string(), start: 0.4, end: 0.9 },
+        ];
+        let mut style = SubtitleStyle::default();
+        style.emoji_map.insert("fire".to_string(), "🔥".to_string());
+        let path = std::env::temp_dir().join("yt-clipper-test-emoji.ass");
+        let path = path.to_str().unwrap();
+        generate_ass_with_word_highlight(&words, path, &style).unwrap();
+        let ass = fs::read_to_string(path).unwrap();
+        let _ = fs::remove_file(path);
+
+        let dialogues: Vec<&str> = ass.lines().filter(|l| l.starts_with("Dialogue:")).collect();
+        // While "FIRE!" is active its emoji follows the highlighted run
+        assert!(dialogues[1].ends_with("FIRE!{\\r} 🔥"), "{}", dialogues[1]);
+        // Otherwise it sits right after the word, in the word's color
+        assert!(dialogues[0].ends_with("FIRE! 🔥"), "{}", dialogues[0]);
+        assert_eq!(ass.matches('🔥').count(), 3);
+        assert_eq!(style.with_emoji("no match here"), "no match here");
+    }
+
+    #[test]
+    fn test_highlight_animation_tags() {
+        let words = vec![
+            TimedWord { text: "halo".to_string(), start: 0.0, end: 0.4 },
+            TimedWord { text: "semua".to_string(), start: 0.4, end: 0.9 },
+        ];
+        let path = std::env::temp_dir().join("yt-clipper-test-animation.ass");
+        let path = path.to_str().unwrap();
+        let ass_for = |animation| {
+            let style = SubtitleStyle {
+                highlight_animation: animation,
+                ..Default::default()
+            };
+            generate_ass_with_word_highlight(&words, path, &style).unwrap();
+            fs::read_to_string(path).unwrap()
+        };
+
+        let pop = ass_for(HighlightAnimation::Pop);
+        assert!(pop.contains("\\fscx110\\fscy110\\t(0,50,\\fscx100\\fscy100)}halo"));
+        assert!(pop.contains("\\fscx90\\fscy90}semua"));
+
+        let fade = ass_for(HighlightAnimation::Fade);
+        assert!(fade.contains("\\alpha&H80&\\t(0,150,\\alpha&H00&)}halo"));
+        assert!(!fade.contains("\\fscx"));
+
+        let slide = ass_for(HighlightAnimation::Slide);
+        assert!(slide.contains("\\fsp8\\t(0,120,\\fsp0)}halo"));
+
+        let none = ass_for(HighlightAnimation::None);
+        assert!(none.contains("{\\c&H00FFFF&}halo{\\r}"));
+        assert!(!none.contains("\\fscx") && !none.contains("\\t("));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_dual_subtitle_ass() {
+        let original = parse_srt_cues(
+            "1\n00:00:00,000 --> 00:00:02,000\nHalo semua\n\n\
+             2\n00:00:02,000 --> 00:00:04,500\napa kabar\n\n",
+        );
+        assert_eq!(original[1].text, "apa kabar");
+        // Translation segmented differently: one cue straddles both, one only overlaps the first
+        let translated = parse_srt_cues(
+            "1\n00:00:00,000 --> 00:00:01,200\nHello\n\n\
+             2\n00:00:01,200 --> 00:00:02,100\neveryone\n\n\
+             3\n00:00:02,300 --> 00:00:04,400\nhow are you\n\n",
+        );
+        assert_eq!(
+            pair_translations(&original, &translated),
+            vec![vec!["Hello", "everyone"], vec!["how are you"]]
+        );
+
+        let path = std::env::temp_dir().join("yt-clipper-test-dual.ass");
+        let path = path.to_str().unwrap();
+        write_dual_ass(&original, &translated, path, &SubtitleStyle::default()).unwrap();
+        let ass = fs::read_to_string(path).unwrap();
+        let _ = fs::remove_file(path);
+
+        assert!(ass.contains("Style: Default,Arial Black,52,"));
+        assert!(ass.contains("Style: Translation,Arial Black,39,&H00CCCCCC,"));
+        assert!(ass.contains(
+            "Dialogue: 0,0:00:00.00,0:00:02.00,Default,,0,0,0,,Halo semua\\N{\\rTranslation}Hello everyone\n"
+        ));
+        assert!(ass.contains(",,apa kabar\\N{\\rTranslation}how are you\n"));
+    }
+
+    #[test]
+    fn test_embedded_subtitle_codec() {
+        assert_eq!(embedded_subtitle_codec("clip.mp4", "sub.ass"), "mov_text");
+        assert_eq!(embedded_subtitle_codec("clip.partial.mkv", "sub.ass"), "ass");
+        assert_eq!(embedded_subtitle_codec("clip.mkv", "sub.srt"), "srt");
+    }
+
+    #[test]
+    fn test_transcribe_tries_every_binary() {
+        let runner = crate::runner::RecordingRunner::new();
+        runner.push_response(CommandOutput::failed("error: unknown argument: --output-json-full"));
+        runner.push_response(CommandOutput::failed("segmentation fault"));
+        runner.push_response(CommandOutput::failed("error while loading shared libraries"));
+        runner.push_response(CommandOutput::failed("error while loading shared libraries"));
+        let config = SubtitleConfig::default();
+        let binaries = vec!["whisper-cli".to_string(), "main".to_string()];
+
+        let err = transcribe_with_binaries(&binaries, |binary| {
+            transcribe_whisper_cpp(binary, "model.bin", "audio.wav", "/nonexistent/sub.ass", &config, &runner)
+        })
+        .unwrap_err()
+        .to_string();
+
+        let programs: Vec<String> = runner.calls().iter().map(|c| c[0].clone()).collect();
+        assert_eq!(programs, ["whisper-cli", "whisper-cli", "main", "main"]);
+        // The unsupported JSON option falls back to SRT on the same binary
+        assert!(runner.calls()[1].contains(&"--output-srt".to_string()));
+        assert!(err.contains("whisper-cli: segmentation fault"));
+        assert!(err.contains("main: error while loading shared libraries"));
+    }
+
+    #[test]
+    fn test_vtt_sidecar_cues() {
+        assert_eq!(format_vtt_time(0.0), "00:00:00.000");
+        assert_eq!(format_vtt_time(3725.5), "01:02:05.500");
+        assert_eq!(SubtitleFormat::from_input(".VTT"), Some(SubtitleFormat::Vtt));
+
+        let path = std::env::temp_dir().join(format!("yt-clipper-vtt-{}.vtt", std::process::id()));
+        let words = [word("Hello", 1.0, 1.4), word("<world>", 1.5, 2.25)];
+        generate_vtt_with_word_timing(&words, path.to_str().unwrap(), &SubtitleStyle::default())
+            .unwrap();
+        let vtt = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert!(vtt.starts_with("WEBVTT\n"));
+        let timings: Vec<&str> = vtt.lines().filter(|l| l.contains(" --> ")).collect();
+        assert_eq!(
+            timings,
+            ["00:00:01.000 --> 00:00:01.400", "00:00:01.500 --> 00:00:02.250"]
+        );
+        for timing in timings {
+            for stamp in timing.split(" --> ") {
+                let bytes = stamp.as_bytes();
+                assert_eq!(stamp.len(), 12);
+                assert_eq!((bytes[2], bytes[5], bytes[8]), (b':', b':', b'.'));
+            }
+        }
+        assert!(vtt.contains("<c.spoken>Hello</c> <c.active>&lt;world&gt;</c>"));
+    }
+
+    #[test]
+    fn test_whisper_cpp_decoding_args() {
+        let runner = crate::runner::RecordingRunner::new();
+        runner.push_response(CommandOutput::failed("no json"));
+        runner.push_response(CommandOutput::failed("no srt"));
+        let config = SubtitleConfig::default()
+            .with_decoding(8, 0.2, false)
+            .with_whisper_threads(Some(6));
+        let binaries = vec!["whisper-cli".to_string()];
+
+        let _ = transcribe_with_binaries(&binaries, |binary| {
+            transcribe_whisper_cpp(binary, "model.bin", "audio.wav", "/nonexistent/sub.ass", &config, &runner)
+        });
+
+        for call in runner.calls() {
+            assert!(call.windows(2).any(|w| w == ["-t", "6"]));
+            assert!(call.windows(2).any(|w| w == ["--beam-size", "8"]));
+            assert!(call.windows(2).any(|w| w == ["--temperature", "0.2"]));
+            assert!(!call.contains(&"--vad".to_string()));
+            assert!(!call.contains(&"--translate".to_string()));
+        }
+        let translate = SubtitleConfig::default().with_task(WhisperTask::Translate);
+        assert!(whisper_cpp_decoding_args(&translate).contains(&"--translate".to_string()));
+        let defaults = SubtitleConfig::default();
+        assert_eq!((defaults.beam_size, defaults.temperature, defaults.vad), (5, 0.0, false));
+        assert!(defaults.thread_count() >= 1);
+        assert_eq!(SubtitleConfig::default().with_whisper_threads(Some(0)).thread_count(), 1);
+    }
+
+    #[test]
+    fn test_subtitle_local_file_paths() {
+        assert_eq!(
+            subtitled_output_path(Path::new("clips/talk.mkv")),
+            Path::new("clips/talk_subtitled.mp4")
+        );
+
+        let runner = crate::runner::RecordingRunner::new();
+        let err = subtitle_local_file(
+            Path::new("/nonexistent/clip.mp4"),
+            &SubtitleConfig::default(),
+            &std::env::temp_dir(),
+            false,
+            &runner,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert!(runner.calls().is_empty());
+    }
+
+    #[test]
+    fn test_is_unsupported_option_error() {
+        assert!(is_unsupported_option_error(
+            "error: unknown argument: --output-json-full",
+            "--output-json-full"
+        ));
+        assert!(!is_unsupported_option_error("failed to load model", "--output-json-full"));
+    }
+
+    fn word(text: &str, start: f64, end: f64) -> TimedWord {
+        TimedWord {
+            text: text.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_split_long_japanese_word() {
+        let text = "今日はとても良い天気ですね散歩に行きましょう";
+        let words = split_long_words(&[word(text, 1.0, 3.2)], MAX_PHRASE_WIDTH);
+
+        assert_eq!(words.len(), 3);
+        assert!(words.iter().all(|w| display_width(&w.text) <= MAX_PHRASE_WIDTH));
+        let rejoined: String = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(rejoined, text);
+        assert_eq!(words[0].start, 1.0);
+        assert!((words[0].end - 2.0).abs() < 1e-9);
+        assert!((words[2].end - 3.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_group_japanese_tokens_without_spaces() {
+        let words = vec![
+            word("今日は", 0.0, 0.5),
+            word("いい", 0.5, 0.8),
+            word("天気", 0.8, 1.0),
+            word("です。", 1.0, 1.4),
+            word("散歩", 1.5, 2.0),
+        ];
+        let phrases = group_into_phrases(&words);
+
+        // Not capped at three "words", broken at the full stop
+        assert_eq!(phrases.len(), 2);
+        assert_eq!(phrases[0].len(), 4);
+        let text = join_words(&phrases[0], |_, w| w.text.clone());
+        assert_eq!(text, "今日はいい天気です。");
+    }
+
+    #[test]
+    fn test_group_arabic_counts_characters() {
+        // 6 + 5 + 5 characters but 12 + 10 + 10 bytes
+        let words = vec![
+            word("السلام", 0.0, 0.4),
+            word("عليكم", 0.4, 0.8),
+            word("ورحمة", 0.8, 1.2),
+            word("الله", 1.2, 1.6),
+        ];
+        let phrases = group_into_phrases(&words);
+
+        assert_eq!(phrases.len(), 2);
+        assert_eq!(phrases[0].len(), 3);
+        let text = join_words(&phrases[0], |_, w| w.text.clone());
+        assert_eq!(text, "السلام عليكم ورحمة");
+    }
+
+    #[test]
+    fn test_subtitle_min_score() {
+        let config = SubtitleConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(config.applies_to_score(0.1));
+
+        let config = config.with_min_score(Some(0.7));
+        assert!(config.applies_to_score(0.7));
+        assert!(!config.applies_to_score(0.69));
+        assert!(!SubtitleConfig::default().with_min_score(Some(0.1)).applies_to_score(0.9));
+    }
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(parse_language("auto"), Some("auto".to_string()));
+        assert_eq!(parse_language("Detect"), Some("auto".to_string()));
+        assert_eq!(parse_language("EN"), Some("en".to_string()));
+        assert_eq!(parse_language("zh-TW"), Some("zh-tw".to_string()));
+        assert_eq!(parse_language(""), None);
+        assert_eq!(parse_language("e\"n"), None);
+    }
+
+    #[test]
+    fn test_parse_detected_language() {
+        let stderr = "whisper_init_state: compute buffer\n\
+                      whisper_full_with_state: auto-detected language: en (p = 0.974531)\n";
+        assert_eq!(parse_detected_language(stderr), Some("en".to_string()));
+        assert_eq!(
+            parse_detected_language("auto-detected language: ja (p = 0.91)"),
+            Some("ja".to_string())
+        );
+        assert_eq!(parse_detected_language("no language here"), None);
     }
 
     #[test]
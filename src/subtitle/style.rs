@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::{Display, EnumString};
+
+/// How words are animated in the generated ASS subtitles
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubtitleEffect {
+    /// Word-by-word highlight (TikTok/CapCut style), needs word-level timestamps
+    #[default]
+    WordHighlight,
+    /// Classic karaoke fill sweeping across each phrase, needs word-level timestamps
+    Karaoke,
+    /// Plain phrase-level subtitles without animation
+    Static,
+}
+
+/// How the active word enters in word-highlight subtitles
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightAnimation {
+    /// Scales up and settles back (neighbouring words are shrunk slightly)
+    #[default]
+    Pop,
+    /// Fades in from half transparent
+    Fade,
+    /// Letters slide together from a wider spacing
+    Slide,
+    /// Color change only, no transform
+    None,
+}
+
+impl HighlightAnimation {
+    /// Parse from user input
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "pop" => Some(HighlightAnimation::Pop),
+            "fade" => Some(HighlightAnimation::Fade),
+            "slide" => Some(HighlightAnimation::Slide),
+            "none" | "off" => Some(HighlightAnimation::None),
+            _ => None,
+        }
+    }
+
+    /// ASS override tags for the active word, given its BGR color
+    pub fn active_tags(&self, color: &str) -> String {
+        match self {
+            // \t = transform over time, \fscx\fscy = scale
+            HighlightAnimation::Pop => {
+                format!("\\c&H{color}&\\fscx110\\fscy110\\t(0,50,\\fscx100\\fscy100)")
+            }
+            HighlightAnimation::Fade => format!("\\c&H{color}&\\alpha&H80&\\t(0,150,\\alpha&H00&)"),
+            // \fsp = extra spacing between letters
+            HighlightAnimation::Slide => format!("\\c&H{color}&\\fsp8\\t(0,120,\\fsp0)"),
+            HighlightAnimation::None => format!("\\c&H{color}&"),
+        }
+    }
+
+    /// Scale tags for words already spoken and words not yet spoken
+    pub fn neighbour_scale_tags(&self) -> (&'static str, &'static str) {
+        match self {
+            HighlightAnimation::Pop => ("\\fscx95\\fscy95", "\\fscx90\\fscy90"),
+            _ => ("", ""),
+        }
+    }
+}
+
+/// Vertical placement of subtitles on the frame
+#[derive(
+    Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString, schemars::JsonSchema,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitlePosition {
+    Top,
+    Center,
+    #[default]
+    Bottom,
+}
+
+impl SubtitlePosition {
+    /// ASS numpad-style `Alignment` value (horizontally centered)
+    pub fn alignment(&self) -> u8 {
+        match self {
+            SubtitlePosition::Top => 8,
+            SubtitlePosition::Center => 5,
+            SubtitlePosition::Bottom => 2,
+        }
+    }
+
+    /// Parse from user input
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "top" => Some(SubtitlePosition::Top),
+            "center" | "centre" | "middle" => Some(SubtitlePosition::Center),
+            "bottom" => Some(SubtitlePosition::Bottom),
+            _ => None,
+        }
+    }
+}
+
+/// Visual style of generated subtitles (maps onto an ASS `Style:` line)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleStyle {
+    pub font_name: String,
+    pub font_size: u32,
+    /// Main text color (ASS `&HAABBGGRR`)
+    pub primary_color: String,
+    /// Karaoke "not yet sung" color (ASS `&HAABBGGRR`)
+    pub secondary_color: String,
+    pub outline_color: String,
+    /// Shadow color, or box color for boxed styles
+    pub back_color: String,
+    pub bold: bool,
+    /// ASS BorderStyle: 1 = outline + shadow, 3 = opaque box, 4 = box behind the whole line (libass)
+    pub border_style: u8,
+    pub outline: u32,
+    pub shadow: u32,
+    pub position: SubtitlePosition,
+    /// Distance from the top or bottom edge (ignored when centered)
+    pub margin_v: u32,
+    pub effect: SubtitleEffect,
+    /// Word-highlight color of the word being spoken (hex RGB, e.g. `#FFFF00`)
+    pub active_color: String,
+    /// Word-highlight color of words already spoken (hex RGB)
+    pub spoken_color: String,
+    /// Word-highlight color of words not yet spoken (hex RGB)
+    pub upcoming_color: String,
+    /// Word-highlight entrance of the active word
+    #[serde(default)]
+    pub highlight_animation: HighlightAnimation,
+    /// Emoji appended after matching words, keyed by word (case-insensitive, surrounding
+    /// punctuation ignored), e.g. `"fire" -> "🔥"`.
+    ///
+    /// libass draws emoji in a single color with the subtitle font, falling back to
+    /// fontconfig: without an emoji font installed (e.g. Noto Color Emoji) they show as boxes.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub emoji_map: HashMap<String, String>,
+}
+
+/// Convert a hex RGB color (`#RRGGBB` or `RRGGBB`) to the BGR order used by ASS color tags.
+///
+/// Returns `None` if the input is not a valid 6-digit hex color.
+pub fn hex_rgb_to_ass_bgr(hex: &str) -> Option<String> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = hex.to_uppercase();
+    Some(format!("{}{}{}", &hex[4..6], &hex[2..4], &hex[0..2]))
+}
+
+impl Default for SubtitleStyle {
+    fn default() -> Self {
+        SubtitlePreset::default().to_style()
+    }
+}
+
+impl SubtitleStyle {
+    /// Format as an ASS `Style:` line with the given style name
+    pub fn to_ass_style_line(&self, name: &str) -> String {
+        format!(
+            "Style: {},{},{},{},{},{},{},{},0,0,0,100,100,0,0,{},{},{},{},20,20,{},1",
+            name,
+            self.font_name,
+            self.font_size,
+            self.primary_color,
+            self.secondary_color,
+            self.outline_color,
+            self.back_color,
+            if self.bold { 1 } else { 0 },
+            self.border_style,
+            self.outline,
+            self.shadow,
+            self.position.alignment(),
+            self.margin_v
+        )
+    }
+
+    /// `" <emoji>"` if `emoji_map` has an entry for `word`, otherwise empty
+    pub fn emoji_suffix(&self, word: &str) -> String {
+        let key = word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        if key.is_empty() {
+            return String::new();
+        }
+        self.emoji_map
+            .iter()
+            .find(|(word, _)| word.to_lowercase() == key)
+            .map(|(_, emoji)| format!(" {}", emoji))
+            .unwrap_or_default()
+    }
+
+    /// `line` with the mapped emoji appended after each matching word
+    pub fn with_emoji(&self, line: &str) -> String {
+        if self.emoji_map.is_empty() {
+            return line.to_string();
+        }
+        line.split(' ')
+            .map(|word| format!("{}{}", word, self.emoji_suffix(word)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// ASS BGR colors for the active, spoken and upcoming words, falling back to the
+    /// default palette for any color that fails to parse
+    pub fn highlight_colors_bgr(&self) -> (String, String, String) {
+        let convert = |hex: &str, fallback: &str| {
+            hex_rgb_to_ass_bgr(hex).unwrap_or_else(|| fallback.to_string())
+        };
+        (
+            convert(&self.active_color, "00FFFF"),
+            convert(&self.spoken_color, "CCCCCC"),
+            convert(&self.upcoming_color, "666666"),
+        )
+    }
+
+    /// Style of the translated line in dual captions: smaller, not bold, and in the
+    /// spoken-word color so it reads as secondary to the original line
+    pub fn translation_style(&self) -> SubtitleStyle {
+        let primary_color = hex_rgb_to_ass_bgr(&self.spoken_color)
+            .map_or_else(|| self.primary_color.clone(), |bgr| format!("&H00{}", bgr));
+        SubtitleStyle {
+            font_size: self.font_size * 3 / 4,
+            bold: false,
+            primary_color,
+            ..self.clone()
+        }
+    }
+
+    /// Whether this style needs word-level timestamps
+    pub fn needs_word_timing(&self) -> bool {
+        !matches!(self.effect, SubtitleEffect::Static)
+    }
+}
+
+/// Ready-made subtitle looks
+#[derive(
+    Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString, schemars::JsonSchema,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum SubtitlePreset {
+    /// Yellow active word with pop animation on white text
+    #[default]
+    TikTokYellow,
+    /// Plain white text with a thin outline, no animation
+    Minimal,
+    /// White text on an opaque background box, no animation
+    Boxed,
+    /// Karaoke fill from white to yellow as each word is spoken
+    Karaoke,
+}
+
+impl SubtitlePreset {
+    /// Build the full style for this preset
+    pub fn to_style(&self) -> SubtitleStyle {
+        match self {
+            SubtitlePreset::TikTokYellow => SubtitleStyle {
+                font_name: "Arial Black".to_string(),
+                font_size: 52,
+                primary_color: "&H00FFFFFF".to_string(),
+                secondary_color: "&H000000FF".to_string(),
+                outline_color: "&H00000000".to_string(),
+                back_color: "&H80000000".to_string(),
+                bold: true,
+                border_style: 1,
+                outline: 4,
+                shadow: 0,
+                position: SubtitlePosition::Bottom,
+                margin_v: 80,
+                effect: SubtitleEffect::WordHighlight,
+                active_color: "#FFFF00".to_string(),
+                spoken_color: "#CCCCCC".to_string(),
+                upcoming_color: "#666666".to_string(),
+                highlight_animation: HighlightAnimation::Pop,
+                emoji_map: HashMap::new(),
+            },
+            SubtitlePreset::Minimal => SubtitleStyle {
+                font_name: "Arial".to_string(),
+                font_size: 44,
+                primary_color: "&H00FFFFFF".to_string(),
+                secondary_color: "&H000000FF".to_string(),
+                outline_color: "&H00000000".to_string(),
+                back_color: "&H80000000".to_string(),
+                bold: false,
+                border_style: 1,
+                outline: 2,
+                shadow: 1,
+                position: SubtitlePosition::Bottom,
+                margin_v: 100,
+                effect: SubtitleEffect::Static,
+                active_color: "#FFFF00".to_string(),
+                spoken_color: "#CCCCCC".to_string(),
+                upcoming_color: "#666666".to_string(),
+                highlight_animation: HighlightAnimation::Pop,
+                emoji_map: HashMap::new(),
+            },
+            SubtitlePreset::Boxed => SubtitleStyle {
+                font_name: "Arial Black".to_string(),
+                font_size: 38,
+                primary_color: "&H00FFFFFF".to_string(),
+                secondary_color: "&H000000FF".to_string(),
+                outline_color: "&H00000000".to_string(),
+                back_color: "&H20000000".to_string(),
+                bold: true,
+                border_style: 4,
+                outline: 0,
+                shadow: 3,
+                position: SubtitlePosition::Bottom,
+                margin_v: 100,
+                effect: SubtitleEffect::Static,
+                active_color: "#FFFF00".to_string(),
+                spoken_color: "#CCCCCC".to_string(),
+                upcoming_color: "#666666".to_string(),
+                highlight_animation: HighlightAnimation::Pop,
+                emoji_map: HashMap::new(),
+            },
+            SubtitlePreset::Karaoke => SubtitleStyle {
+                font_name: "Arial Black".to_string(),
+                font_size: 52,
+                primary_color: "&H0000FFFF".to_string(),
+                secondary_color: "&H00FFFFFF".to_string(),
+                outline_color: "&H00000000".to_string(),
+                back_color: "&H80000000".to_string(),
+                bold: true,
+                border_style: 1,
+                outline: 4,
+                shadow: 0,
+                position: SubtitlePosition::Bottom,
+                margin_v: 80,
+                effect: SubtitleEffect::Karaoke,
+                active_color: "#FFFF00".to_string(),
+                spoken_color: "#CCCCCC".to_string(),
+                upcoming_color: "#666666".to_string(),
+                highlight_animation: HighlightAnimation::Pop,
+                emoji_map: HashMap::new(),
+            },
+        }
+    }
+
+    /// Get human-readable description
+    pub fn description(&self) -> &'static str {
+        match self {
+            SubtitlePreset::TikTokYellow => "TikTok (yellow word highlight)",
+            SubtitlePreset::Minimal => "Minimal (plain white, no animation)",
+            SubtitlePreset::Boxed => "Boxed (opaque background box)",
+            SubtitlePreset::Karaoke => "Karaoke (fill as words are spoken)",
+        }
+    }
+
+    /// Parse from user input
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "tiktok" | "tiktok-yellow" | "tiktokyellow" => Some(SubtitlePreset::TikTokYellow),
+            "minimal" => Some(SubtitlePreset::Minimal),
+            "boxed" | "box" => Some(SubtitlePreset::Boxed),
+            "karaoke" => Some(SubtitlePreset::Karaoke),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_from_input() {
+        assert_eq!(SubtitlePreset::from_input("tiktok"), Some(SubtitlePreset::TikTokYellow));
+        assert_eq!(SubtitlePreset::from_input("Minimal"), Some(SubtitlePreset::Minimal));
+        assert_eq!(SubtitlePreset::from_input("boxed"), Some(SubtitlePreset::Boxed));
+        assert_eq!(SubtitlePreset::from_input("karaoke"), Some(SubtitlePreset::Karaoke));
+        assert_eq!(SubtitlePreset::from_input("fancy"), None);
+    }
+
+    #[test]
+    fn test_tiktok_style_line() {
+        assert_eq!(
+            SubtitlePreset::TikTokYellow.to_style().to_ass_style_line("Default"),
+            "Style: Default,Arial Black,52,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,1,0,0,0,100,100,0,0,1,4,0,2,20,20,80,1"
+        );
+    }
+
+    #[test]
+    fn test_word_timing_requirement() {
+        assert!(SubtitlePreset::TikTokYellow.to_style().needs_word_timing());
+        assert!(SubtitlePreset::Karaoke.to_style().needs_word_timing());
+        assert!(!SubtitlePreset::Minimal.to_style().needs_word_timing());
+        assert!(!SubtitlePreset::Boxed.to_style().needs_word_timing());
+    }
+
+    #[test]
+    fn test_highlight_animation_from_input() {
+        assert_eq!(HighlightAnimation::from_input("Fade"), Some(HighlightAnimation::Fade));
+        assert_eq!(HighlightAnimation::from_input("off"), Some(HighlightAnimation::None));
+        assert_eq!(HighlightAnimation::from_input("bounce"), None);
+        assert_eq!(HighlightAnimation::default().to_string(), "pop");
+    }
+
+    #[test]
+    fn test_position_alignment() {
+        let mut style = SubtitlePreset::TikTokYellow.to_style();
+        assert_eq!(style.position, SubtitlePosition::Bottom);
+
+        style.position = SubtitlePosition::Top;
+        assert!(style.to_ass_style_line("Default").ends_with(",1,4,0,8,20,20,80,1"));
+
+        style.position = SubtitlePosition::Center;
+        assert!(style.to_ass_style_line("Default").ends_with(",1,4,0,5,20,20,80,1"));
+
+        assert_eq!(SubtitlePosition::from_input("middle"), Some(SubtitlePosition::Center));
+        assert_eq!(SubtitlePosition::from_input("left"), None);
+    }
+
+    #[test]
+    fn test_hex_rgb_to_ass_bgr() {
+        assert_eq!(hex_rgb_to_ass_bgr("#FFFF00"), Some("00FFFF".to_string()));
+        assert_eq!(hex_rgb_to_ass_bgr("ff8000"), Some("0080FF".to_string()));
+        assert_eq!(hex_rgb_to_ass_bgr("#123456"), Some("563412".to_string()));
+        assert_eq!(hex_rgb_to_ass_bgr("#FFF"), None);
+        assert_eq!(hex_rgb_to_ass_bgr("#GG0000"), None);
+    }
+
+    #[test]
+    fn test_highlight_colors_fallback() {
+        let mut style = SubtitlePreset::TikTokYellow.to_style();
+        assert_eq!(
+            style.highlight_colors_bgr(),
+            ("00FFFF".to_string(), "CCCCCC".to_string(), "666666".to_string())
+        );
+
+        style.active_color = "#FF0000".to_string();
+        style.upcoming_color = "not-a-color".to_string();
+        let (active, _, upcoming) = style.highlight_colors_bgr();
+        assert_eq!(active, "0000FF");
+        assert_eq!(upcoming, "666666");
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumString};
+
+use super::{SubtitleConfig, TimedWord, WhisperTask};
+
+/// Environment variable overriding the transcript cache directory
+pub const TRANSCRIPT_CACHE_DIR_ENV: &str = "YT_CLIPPER_TRANSCRIPT_CACHE";
+
+/// How cached word-level transcripts are used
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum TranscriptCache {
+    /// Load a cached transcript when there is one, otherwise transcribe and cache it
+    #[default]
+    Reuse,
+    /// Always transcribe, replacing the cached transcript
+    Refresh,
+    /// Only restyle from the cache; fail instead of transcribing
+    Only,
+}
+
+/// Directory holding cached transcripts (`yt-clipper-rust/transcripts` in the user cache)
+pub fn transcript_cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(TRANSCRIPT_CACHE_DIR_ENV).filter(|d| !d.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("yt-clipper-rust")
+        .join("transcripts")
+}
+
+/// 64-bit FNV-1a; stable across Rust versions, unlike `DefaultHasher`
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Cache key for transcribing `audio` with `config`.
+///
+/// Keyed on the extracted audio rather than the clip, so re-cropping or re-encoding the
+/// same moment still hits the cache. Settings that change the transcript are part of the
+/// key; styling is not.
+pub fn cache_key(audio: &[u8], config: &SubtitleConfig) -> String {
+    let mut settings = format!(
+        "{}|{}|{}|{}|{}",
+        config.model.ggml_filename(),
+        config.language,
+        config.beam_size,
+        config.temperature,
+        config.vad
+    );
+    // Appended rather than always included so transcripts cached before tasks existed still hit
+    if config.task == WhisperTask::Translate {
+        settings.push_str("|translate");
+    }
+    let hash = fnv1a(0xcbf2_9ce4_8422_2325, settings.as_bytes());
+    format!("{:016x}", fnv1a(hash, audio))
+}
+
+fn cache_file(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.json", key))
+}
+
+/// Cached words for `key`, if any (unreadable entries count as missing)
+pub fn load_words(dir: &Path, key: &str) -> Option<Vec<TimedWord>> {
+    let content = fs::read_to_string(cache_file(dir, key)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Cache `words` under `key`
+pub fn save_words(dir: &Path, key: &str, words: &[TimedWord]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(cache_file(dir, key), serde_json::to_string(words)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WhisperModel;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = std::env::temp_dir().join("yt-clipper-test-transcripts");
+        let config = SubtitleConfig::default();
+        let key = cache_key(b"RIFF audio", &config);
+        assert_eq!(key, cache_key(b"RIFF audio", &config));
+        assert_ne!(key, cache_key(b"RIFF other", &config));
+        let large = SubtitleConfig {
+            model: WhisperModel::Large,
+            ..SubtitleConfig::default()
+        };
+        assert_ne!(key, cache_key(b"RIFF audio", &large));
+        let translated = SubtitleConfig::default().with_task(WhisperTask::Translate);
+        assert_ne!(key, cache_key(b"RIFF audio", &translated));
+
+        let words = vec![TimedWord {
+            text: "halo".to_string(),
+            start: 0.5,
+            end: 0.9,
+        }];
+        save_words(&dir, &key, &words).unwrap();
+        assert_eq!(load_words(&dir, &key), Some(words));
+        assert_eq!(load_words(&dir, "missing"), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Recent requests considered when computing the failure rate
+pub const THROTTLE_WINDOW: usize = 10;
+/// Failure rate from which requests back off
+pub const BACKOFF_FAILURE_RATE: f64 = 0.3;
+/// First delay once backing off; each further failure doubles it
+pub const MIN_BACKOFF: Duration = Duration::from_secs(2);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Default)]
+struct ThrottleState {
+    /// Latest outcomes, oldest first (`true` = success)
+    outcomes: VecDeque<bool>,
+    delay: Duration,
+}
+
+impl ThrottleState {
+    fn failure_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        let failures = self.outcomes.iter().filter(|success| !**success).count();
+        failures as f64 / self.outcomes.len() as f64
+    }
+}
+
+/// Adaptive backoff shared by every request to YouTube (heatmap fetches and downloads).
+///
+/// Once the failure rate of the last `THROTTLE_WINDOW` requests reaches
+/// `BACKOFF_FAILURE_RATE` (YouTube answering 429s or bot checks), each failure doubles the
+/// delay before the next request and `concurrency` scales parallel work down. Successes
+/// halve the delay again once the rate has dropped.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    state: Mutex<ThrottleState>,
+}
+
+impl Throttle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a request and adjust the delay
+    pub fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.outcomes.push_back(success);
+        if state.outcomes.len() > THROTTLE_WINDOW {
+            state.outcomes.pop_front();
+        }
+
+        let throttled = state.failure_rate() >= BACKOFF_FAILURE_RATE;
+        state.delay = match (success, throttled) {
+            (false, true) => (state.delay * 2).clamp(MIN_BACKOFF, MAX_BACKOFF),
+            (true, false) if state.delay > MIN_BACKOFF => state.delay / 2,
+            (true, false) => Duration::ZERO,
+            _ => state.delay,
+        };
+    }
+
+    /// Share of the recent requests that failed
+    pub fn failure_rate(&self) -> f64 {
+        self.state.lock().unwrap().failure_rate()
+    }
+
+    /// How long to wait before the next request
+    pub fn delay(&self) -> Duration {
+        self.state.lock().unwrap().delay
+    }
+
+    /// How many of `max` parallel jobs or fragments to run: fewer the more requests fail,
+    /// but always at least one
+    pub fn concurrency(&self, max: usize) -> usize {
+        if self.delay().is_zero() {
+            return max;
+        }
+        let share = 1.0 - self.failure_rate();
+        ((max as f64 * share).floor() as usize).clamp(1, max.max(1))
+    }
+
+    /// Sleep for the current delay (blocking; async callers sleep on `delay` themselves)
+    pub fn wait(&self) {
+        let delay = self.delay();
+        if !delay.is_zero() {
+            tracing::info!("YouTube is throttling requests, waiting {}s...", delay.as_secs());
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Whether yt-dlp's stderr shows YouTube throttling it: a 429, or a "confirm you're not a
+/// bot" check
+pub fn is_rate_limited(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    ["http error 429", "too many requests", "not a bot"]
+        .iter()
+        .any(|needle| stderr.contains(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle_after(outcomes: &[bool]) -> Throttle {
+        let throttle = Throttle::new();
+        for &success in outcomes {
+            throttle.record(success);
+        }
+        throttle
+    }
+
+    #[test]
+    fn test_backs_off_and_relaxes() {
+        // Occasional failures don't slow anything down
+        let throttle = throttle_after(&[true, true, false, true, true, true, true]);
+        assert_eq!(throttle.delay(), Duration::ZERO);
+        assert_eq!(throttle.concurrency(4), 4);
+
+        // A burst of failures doubles the delay each time and cuts concurrency
+        let throttle = throttle_after(&[true, true, false, false, false]);
+        assert_eq!(throttle.failure_rate(), 0.6);
+        assert_eq!(throttle.delay(), MIN_BACKOFF * 4);
+        assert_eq!(throttle.concurrency(4), 1);
+        assert_eq!(throttle.concurrency(0), 1);
+
+        let throttle = throttle_after(&[false; 20]);
+        assert_eq!(throttle.delay(), MAX_BACKOFF);
+
+        // Successes keep the delay while the window is still mostly failures, then halve it
+        throttle.record(true);
+        assert_eq!(throttle.delay(), MAX_BACKOFF);
+        for _ in 0..7 {
+            throttle.record(true);
+        }
+        assert!(throttle.delay() < MAX_BACKOFF);
+        for _ in 0..10 {
+            throttle.record(true);
+        }
+        assert_eq!(throttle.delay(), Duration::ZERO);
+        assert_eq!(throttle.failure_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_is_rate_limited() {
+        assert!(is_rate_limited("ERROR: unable to download video data: HTTP Error 429: Too Many Requests"));
+        assert!(is_rate_limited("ERROR: [youtube] abc: Sign in to confirm you're not a bot."));
+        assert!(!is_rate_limited("ERROR: [youtube] abc: Video unavailable"));
+        assert!(!is_rate_limited("ERROR: Requested format is not available"));
+    }
+}
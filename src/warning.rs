@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use strum::Display;
+
+/// What kind of soft failure a `Warning` reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, schemars::JsonSchema)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningKind {
+    /// Shorter than `MIN_CLIP_DURATION` once clamped to the video, so it was skipped
+    ClipTooShort,
+    /// Download, crop or audio extraction failed or timed out; the clip was skipped
+    ClipFailed,
+    /// Subtitles could not be generated or added; the clip was saved without them
+    SubtitleFailed,
+    /// Subtitles were requested but can't apply (muted or audio-only clips)
+    SubtitleSkipped,
+    /// The clip's download came back empty, so it was retried ending earlier (the reported
+    /// duration of long livestream VODs can overshoot)
+    RangePastEnd,
+    /// `trim_silence` couldn't transcribe the clip, so it kept its padded range
+    SilenceTrimFailed,
+    /// The whisper model is known to transcribe the subtitle language poorly
+    SubtitleModelTooSmall,
+    /// Two clips share most of their footage (see `ProcessOptions::max_overlap`)
+    ClipsOverlap,
+    /// The uncropped copy (`keep_horizontal`) could not be saved
+    HorizontalCopyFailed,
+    /// The looping preview (`preview`) could not be made
+    PreviewFailed,
+    /// ffmpeg has no NVENC encoder, so clips were encoded on the CPU
+    GpuUnavailable,
+    /// The video title could not be fetched for metadata, the manifest or the header
+    TitleUnavailable,
+    /// `download_full_first` fell back to per-clip downloads
+    FullDownloadFailed,
+    ReelFailed,
+    ManifestFailed,
+    /// The heatmap chart (`heatmap_png`) could not be drawn, or the build lacks the feature
+    HeatmapPngFailed,
+}
+
+impl WarningKind {
+    /// Whether something that was asked for is missing from the result (a clip, its
+    /// subtitles, the reel, ...), as opposed to a fallback or a heads-up
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            WarningKind::ClipFailed
+                | WarningKind::SubtitleFailed
+                | WarningKind::HorizontalCopyFailed
+                | WarningKind::PreviewFailed
+                | WarningKind::ReelFailed
+                | WarningKind::ManifestFailed
+                | WarningKind::HeatmapPngFailed
+        )
+    }
+}
+
+/// A soft failure: the run went on, but its result is not everything that was asked for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// Clip the warning is about, if it is about one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clip: Option<usize>,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            clip: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn for_clip(mut self, index: usize) -> Self {
+        self.clip = Some(index);
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.clip {
+            Some(index) => write!(f, "Warning (clip {}): {}", index, self.message),
+            None => write!(f, "Warning: {}", self.message),
+        }
+    }
+}
+
+/// Log `warning` and collect it
+pub fn warn(warnings: &mut Vec<Warning>, warning: Warning) {
+    tracing::warn!("{}", warning);
+    warnings.push(warning);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_serialization() {
+        let warning = Warning::new(WarningKind::SubtitleFailed, "whisper crashed").for_clip(2);
+        assert_eq!(warning.to_string(), "Warning (clip 2): whisper crashed");
+        assert_eq!(
+            serde_json::to_value(&warning).unwrap(),
+            serde_json::json!({"kind": "subtitle-failed", "clip": 2, "message": "whisper crashed"})
+        );
+
+        let warning = Warning::new(WarningKind::ReelFailed, "concat failed");
+        assert_eq!(
+            serde_json::to_value(&warning).unwrap(),
+            serde_json::json!({"kind": "reel-failed", "message": "concat failed"})
+        );
+    }
+}